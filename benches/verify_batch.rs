@@ -0,0 +1,38 @@
+//! Compares `support::verify_batch` (ed25519 batch verification) against `support::verify_each`
+//! (one Rayon task per signature) for blocks of 1k–10k transfer extrinsics.
+//!
+//! Run with `cargo bench --bench verify_batch`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rust_state_machine::{RuntimeCall, balances, support, types};
+use support::keyring::AccountKeyring::{Alice, Bob};
+
+fn block_of(n: u32) -> Vec<types::Extrinsic> {
+	let sk = Alice.signing_key();
+	(0..n)
+		.map(|nonce| {
+			let call =
+				RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 1 });
+			support::UncheckedExtrinsic::new_signed(&sk, nonce, u64::MAX, call)
+		})
+		.collect()
+}
+
+fn bench_verify(c: &mut Criterion) {
+	let mut group = c.benchmark_group("verify");
+	group.sample_size(10);
+	for n in [1_000u32, 2_500, 5_000, 10_000] {
+		let exts = block_of(n);
+		group.throughput(Throughput::Elements(n as u64));
+		group.bench_with_input(BenchmarkId::new("batch", n), &exts, |b, exts| {
+			b.iter(|| support::verify_batch(exts))
+		});
+		group.bench_with_input(BenchmarkId::new("each", n), &exts, |b, exts| {
+			b.iter(|| support::verify_each(exts))
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
@@ -3,13 +3,13 @@ use quote::quote;
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
-	let CallDef { pallet_struct, methods } = def;
+	let CallDef { pallet_struct, methods, context } = def;
 
 	// This is a vector of all the callable function names.
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
 
 	// This is a nested vector of all the arguments for each of the functions in `fn_name`. It does
-	// not include the `self` or `caller: T::AccountId` parameter, which we always assume are the
+	// not include the `self` or `origin: OriginFor<T>` parameter, which we always assume are the
 	// first two parameters to these calls.
 	let args_name = methods
 		.iter()
@@ -23,14 +23,37 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
+	// The context argument passed to each function, for those that take it.
+	let context_arg = methods
+		.iter()
+		.map(|method| {
+			if method.takes_context {
+				quote! { context, }
+			} else {
+				quote! {}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// Pallets whose calls take a context implement `DispatchWithContext` instead of `Dispatch`,
+	// and receive the context from the runtime.
+	let dispatch_trait = match &context {
+		Some(context) => quote! { crate::support::DispatchWithContext<#context> },
+		None => quote! { crate::support::Dispatch },
+	};
+	let context_param = match &context {
+		Some(context) => quote! { context: &mut #context, },
+		None => quote! {},
+	};
+
 	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
-	// and the `Dispatch` trait logic to route a `caller` to access those functions.
+	// and the `Dispatch` trait logic to route an `origin` to access those functions.
 	let dispatch_impl = quote! {
 		// The callable functions exposed by this pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
-		#[derive(parity_scale_codec::Encode, parity_scale_codec::Decode)]
+		#[derive(Debug, parity_scale_codec::Encode, parity_scale_codec::Decode)]
 		pub enum Call<T: Config> {
 			#(
 				#fn_name { #( #args_name: #args_type),* },
@@ -38,18 +61,24 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		}
 
 		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
-		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountId;
+		// appropriate function call with all arguments, including the `origin`.
+		impl<T: Config> #dispatch_trait for #pallet_struct<T> {
+			type Origin = crate::system::OriginFor<T>;
 			type Call = Call<T>;
 
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
+			fn dispatch(
+				&mut self,
+				#context_param
+				origin: Self::Origin,
+				call: Self::Call,
+			) -> crate::support::DispatchResult {
 				match call {
 					#(
 						Call::#fn_name { #( #args_name ),* } => {
 							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
+								// Note that we assume the first argument of every call is the `origin`.
+								origin,
+								#context_arg
 								#( #args_name ),*
 							)?;
 						},
@@ -4,7 +4,7 @@ use syn::spanned::Spanned;
 // Custom keywords we match to when parsing the calls in a pallet.
 mod keyword {
 	syn::custom_keyword!(T);
-	syn::custom_keyword!(AccountId);
+	syn::custom_keyword!(OriginFor);
 }
 
 /// This object will collect all the information we need to keep while parsing the callable
@@ -16,6 +16,9 @@ pub struct CallDef {
 	pub pallet_struct: syn::Ident,
 	/// This is a list of the callable functions exposed by this pallet. See `CallVariantDef`.
 	pub methods: Vec<CallVariantDef>,
+	/// The type behind the `&mut` context argument, if any call takes one. All calls that take a
+	/// context must agree on its type.
+	pub context: Option<Box<syn::Type>>,
 }
 
 /// This is the metadata we keep about each callable function in our pallet.
@@ -25,6 +28,8 @@ pub struct CallVariantDef {
 	pub name: syn::Ident,
 	/// Information on args of the function: `(name, type)`.
 	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	/// Whether the function takes the context argument right after `origin`.
+	pub takes_context: bool,
 }
 
 impl CallDef {
@@ -45,6 +50,7 @@ impl CallDef {
 
 		// Here is where we will store all the callable functions.
 		let mut methods = vec![];
+		let mut context: Option<Box<syn::Type>> = None;
 		for item in item_impl.items {
 			if let syn::ImplItem::Fn(method) = item {
 				// Here is where we will store all the args for each callable functions.
@@ -59,23 +65,44 @@ impl CallDef {
 					},
 				}
 
-				// The second argument should be the `caller: T::AccountId` argument.
+				// The second argument should be the `origin: OriginFor<T>` argument.
 				match method.sig.inputs.iter().skip(1).next() {
 					Some(syn::FnArg::Typed(arg)) => {
 						// Here we specifically check that this argument is as we expect for
-						// `caller: T::AccountId`.
-						check_caller_arg(arg)?;
+						// `origin: OriginFor<T>`.
+						check_origin_arg(arg)?;
 					},
 					_ => {
-						let msg = "Invalid call, second argument should be `caller: T::AccountId`";
+						let msg = "Invalid call, second argument should be `origin: OriginFor<T>`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
 				}
 
 				let fn_name = method.sig.ident.clone();
 
-				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
-				for arg in method.sig.inputs.iter().skip(2) {
+				// An optional third argument `name: &mut Type` is the context the runtime passes
+				// in, e.g. another pallet. It is not part of the `Call` variant.
+				let mut takes_context = false;
+				if let Some(syn::FnArg::Typed(arg)) = method.sig.inputs.iter().nth(2) {
+					if let Some(ty) = context_type(&arg.ty) {
+						match &context {
+							Some(existing)
+								if existing.to_token_stream().to_string() !=
+									ty.to_token_stream().to_string() =>
+							{
+								let msg = "Invalid call, all calls must take the same context type";
+								return Err(syn::Error::new(arg.ty.span(), msg))
+							},
+							Some(_) => {},
+							None => context = Some(ty),
+						}
+						takes_context = true;
+					}
+				}
+
+				// Parsing the rest of the args. Skipping `self`, `origin` and any context.
+				let skip = if takes_context { 3 } else { 2 };
+				for arg in method.sig.inputs.iter().skip(skip) {
 					// All arguments should be typed.
 					let arg = if let syn::FnArg::Typed(arg) = arg {
 						arg
@@ -83,6 +110,13 @@ impl CallDef {
 						unreachable!("All args should be typed.");
 					};
 
+					// Call arguments are stored in the `Call` enum, so they must be owned.
+					if let syn::Type::Reference(_) = &*arg.ty {
+						let msg = "Invalid call, only a context right after `origin` may be a \
+							reference";
+						return Err(syn::Error::new(arg.ty.span(), msg))
+					}
+
 					// Extract the name of the argument.
 					let arg_ident = if let syn::Pat::Ident(pat) = &*arg.pat {
 						pat.ident.clone()
@@ -96,42 +130,51 @@ impl CallDef {
 				}
 
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				methods.push(CallVariantDef { name: fn_name, args, takes_context });
 			}
 		}
 
 		// Return all callable functions for this pallet.
-		Ok(Self { pallet_struct, methods })
+		Ok(Self { pallet_struct, methods, context })
+	}
+}
+
+/// The `Type` in a `&mut Type` context argument.
+fn context_type(ty: &syn::Type) -> Option<Box<syn::Type>> {
+	match ty {
+		syn::Type::Reference(r) if r.mutability.is_some() => Some(r.elem.clone()),
+		_ => None,
 	}
 }
 
-/// Check caller arg is exactly: `caller: T::AccountId`.
+/// Check origin arg is exactly: `origin: OriginFor<T>`.
 ///
 /// This is kept strict to keep the code simple.
-pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
+pub fn check_origin_arg(arg: &syn::PatType) -> syn::Result<()> {
 	pub struct CheckDispatchableFirstArg;
 	impl syn::parse::Parse for CheckDispatchableFirstArg {
 		fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+			input.parse::<keyword::OriginFor>()?;
+			input.parse::<syn::Token![<]>()?;
 			input.parse::<keyword::T>()?;
-			input.parse::<syn::Token![::]>()?;
-			input.parse::<keyword::AccountId>()?;
+			input.parse::<syn::Token![>]>()?;
 			Ok(Self)
 		}
 	}
 
-	// This checks the arg name is `caller` or `_caller`.
+	// This checks the arg name is `origin` or `_origin`.
 	if let syn::Pat::Ident(ident) = &*arg.pat {
-		// We also support the name as `_caller` for when the variable is unused.
-		if &ident.ident != "caller" && &ident.ident != "_caller" {
-			let msg = "Invalid name for second parameter: expected `caller: T::AccountId`";
+		// We also support the name as `_origin` for when the variable is unused.
+		if &ident.ident != "origin" && &ident.ident != "_origin" {
+			let msg = "Invalid name for second parameter: expected `origin: OriginFor<T>`";
 			return Err(syn::Error::new(ident.span(), msg))
 		}
 	}
 
-	// This checks the type is `T::AccountId` with `CheckDispatchableFirstArg`
+	// This checks the type is `OriginFor<T>` with `CheckDispatchableFirstArg`
 	let ty = &arg.ty;
 	syn::parse2::<CheckDispatchableFirstArg>(ty.to_token_stream()).map_err(|e| {
-		let msg = "Invalid type for second parameter: expected `caller: T::AccountId`";
+		let msg = "Invalid type for second parameter: expected `origin: OriginFor<T>`";
 		let mut err = syn::Error::new(ty.span(), msg);
 		err.combine(e);
 		err
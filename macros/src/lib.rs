@@ -1,6 +1,18 @@
 mod call;
 mod runtime;
 
+/// Expand a pallet's callable functions.
+///
+/// Each function takes `&mut self` and `origin: OriginFor<T>`, then its arguments. This generates
+/// an `enum Call` with one variant per function, holding its arguments, and implements
+/// `support::Dispatch` to route each variant to its function. Functions check the origin
+/// themselves, with `support::ensure_signed` or `support::ensure_root`.
+///
+/// A function may take a context right after `origin`, as `name: &mut Type`: state from another
+/// pallet that the runtime hands in, e.g. `currency: &mut T::Currency`. The context is not part
+/// of the `Call` variant. If any function takes one, the pallet implements
+/// `support::DispatchWithContext<Type>` instead, and its runtime field needs a matching
+/// `#[context(...)]` attribute.
 #[proc_macro_attribute]
 pub fn call(
 	attr: proc_macro::TokenStream,
@@ -16,7 +28,11 @@ pub fn call(
 ///   included in the runtime.
 /// - `fn execute_block()` - which handles basic logic for executing a block of extrinsics. It does
 ///   basic actions like incrementing the block number and checking the block to be executed has a
-///   valid block number.
+///   valid block number. It then calls `support::RuntimeHooks::on_initialize`, which the runtime
+///   must implement, before the extrinsics. Finally it checks `support::RuntimeHooks::state_root`
+///   against the header's.
+/// - `fn author_block()` - which executes a block the same way, but fills in the header's state
+///   root instead of checking it, for the node producing the block.
 ///
 /// This also generates code needed for dispatching calls to the pallets:
 /// - Note: For simplicity, we assume that the system pallet is not callable.
@@ -25,6 +41,12 @@ pub fn call(
 /// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet. Basic
 ///   logic like incrementing the nonce of the user is included in the generated code. The system
 ///   pallet is not included.
+/// - a pallet field marked `#[context(other_pallet)]` is dispatched through
+///   `support::DispatchWithContext`, with `&mut self.other_pallet` as the context its calls
+///   take (see `call`).
+/// - a pallet field marked `#[dispatches_as_root]` implements `support::DispatchesAsRoot`: after
+///   each of its calls, the runtime dispatches the call it hands back as `RuntimeOrigin::Root`,
+///   then reports the result to it.
 #[proc_macro_attribute]
 pub fn runtime(
 	attr: proc_macro::TokenStream,
@@ -3,10 +3,50 @@ use quote::quote;
 
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { runtime_struct, pallets, contexts, root_dispatchers } = def;
 
 	// This is a vector of all the pallet names, not including system.
 	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+	// How each pallet is dispatched: through `Dispatch`, or through `DispatchWithContext` with
+	// the pallet named in its `#[context(...)]` attribute.
+	let pallet_dispatch = pallet_names
+		.iter()
+		.zip(&contexts)
+		.map(|(name, context)| match context {
+			Some(context) => quote! {
+				crate::support::DispatchWithContext::dispatch(
+					&mut self.#name,
+					&mut self.#context,
+					origin,
+					call,
+				)?;
+			},
+			None => quote! { self.#name.dispatch(origin, call)?; },
+		})
+		.collect::<Vec<_>>();
+	// For pallets marked `#[dispatches_as_root]`, dispatch the call a call left behind as root
+	// straight away, and report back how it went.
+	let root_dispatch = pallet_names
+		.iter()
+		.zip(&root_dispatchers)
+		.map(|(name, dispatches_as_root)| {
+			if !dispatches_as_root {
+				return quote! {}
+			}
+			quote! {
+				if let Some((caller, call)) =
+					crate::support::DispatchesAsRoot::take_root_call(&mut self.#name)
+				{
+					let result = self.dispatch(crate::support::RuntimeOrigin::Root, call);
+					crate::support::DispatchesAsRoot::root_call_dispatched(
+						&mut self.#name,
+						caller,
+						result,
+					);
+				}
+			}
+		})
+		.collect::<Vec<_>>();
 	// This is a vector of all the pallet types, not including system.
 	let pallet_types = pallets.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>();
 
@@ -24,50 +64,138 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				}
 			}
 
-			// Execute a block of extrinsics. Increments the block number.
-			//
-			// Signature verification is done in parallel via `crate::support::verify_batch`
-			// (backed by Rayon) before the sequential state-transition loop. This mirrors
-			// the block-author pipeline in production runtimes where signature checks are
-			// CPU-bound and embarrassingly parallel.
+			// Import a block from another author: apply it, then check the state it leads to is the
+			// one its header commits to. A rejected block leaves no trace.
 			pub fn execute_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
+				let expected = block.header.state_root;
+				self.transactional(|runtime| {
+					if runtime.apply_block(block)? != expected {
+						return Err(&"block state root does not match the resulting state")
+					}
+					Ok(())
+				})
+			}
+
+			// Author a block: apply it and return it with the resulting state root filled in, ready
+			// to be sent to other nodes. Whatever `state_root` the header held is ignored.
+			pub fn author_block(
+				&mut self,
+				mut block: types::Block,
+			) -> Result<types::Block, &'static str> {
+				// `RuntimeCall` is not `Clone`; the block is copied through its encoding instead.
+				let encoded = parity_scale_codec::Encode::encode(&block);
+				let copy = <types::Block as parity_scale_codec::Decode>::decode(&mut &encoded[..])
+					.expect("a block decodes from its own encoding");
+				block.header.state_root = self.transactional(|runtime| runtime.apply_block(copy))?;
+				Ok(block)
+			}
+
+			// Run `f` as one unit: its storage writes are buffered and only written out if it
+			// succeeds. On failure they are dropped and the pallets restored from a snapshot taken
+			// beforehand, which also brings back state storage does not hold, such as each
+			// pallet's current block and cached hashes.
+			fn transactional<R>(
+				&mut self,
+				f: impl FnOnce(&mut Self) -> Result<R, &'static str>,
+			) -> Result<R, &'static str> {
+				let snapshot = self.clone();
+				let result = crate::support::with_storage_layer(|| f(self));
+				if result.is_err() {
+					*self = snapshot;
+				}
+				result
+			}
+
+			// Execute a block of extrinsics. Increments the block number, records the timestamp and
+			// returns the new state root, which it also records.
+			//
+			// Signature verification is done up front via `crate::support::verify_batch`
+			// (ed25519 batch verification, Rayon for everything else) before the sequential
+			// state-transition loop. This mirrors the block-author pipeline in production
+			// runtimes where signature checks are CPU-bound and independent of state.
+			fn apply_block(&mut self, block: types::Block) -> Result<[u8; 32], &'static str> {
 				self.system.inc_block_number();
 				if block.header.block_number != self.system.block_number() {
 					return Err(&"block number does not match what is expected")
 				}
+				if block.header.timestamp < self.system.timestamp() {
+					return Err(&"block timestamp is earlier than its parent's")
+				}
+				let drift = crate::support::MAX_TIMESTAMP_DRIFT_MILLIS;
+				if block.header.timestamp > crate::support::now_millis() + drift {
+					return Err(&"block timestamp is too far in the future")
+				}
+				self.system.set_timestamp(block.header.timestamp);
+				crate::support::RuntimeHooks::on_initialize(self);
 
-				// Pass 1: verify all signatures in parallel.
-				let verify_results = crate::support::verify_batch(&block.extrinsics);
+				// Pass 1: verify all signatures not already checked at mempool admission.
+				let verify_results = crate::support::verify_batch_cached(
+					&block.extrinsics,
+					crate::support::verified_cache(),
+				);
 
-				// Pass 2: sequential nonce-check + state-transition.
+				// Pass 2: sequential expiry, replay and nonce checks + state-transition. Every
+				// extrinsic, applied or skipped, is indexed by hash with its outcome; that index is
+				// what catches a replay once reaping has reset the signer's nonce.
 				for (i, (ext, sig_result)) in
 					block.extrinsics.into_iter().zip(verify_results).enumerate()
 				{
-					if let Err(e) = sig_result {
+					let hash = ext.hash();
+					let encoded = parity_scale_codec::Encode::encode(&ext);
+
+					let outcome = if let Err(e) = sig_result {
 						eprintln!(
 							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: bad signature — {e}",
 							block.header.block_number, i
 						);
-						continue;
-					}
-
-					if self.system.nonce(&ext.signer) != ext.nonce {
+						crate::support::ExtrinsicOutcome::BadSignature(e.to_string())
+					} else if block.header.timestamp > ext.valid_until {
+						eprintln!(
+							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: expired",
+							block.header.block_number, i
+						);
+						crate::support::ExtrinsicOutcome::Expired
+					} else if crate::support::extrinsic_record::<types::BlockNumber>(&hash)
+						.is_some_and(|record| record.outcome.is_dispatched())
+					{
+						eprintln!(
+							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: already dispatched",
+							block.header.block_number, i
+						);
+						crate::support::ExtrinsicOutcome::Duplicate
+					} else if self.system.nonce(&ext.signer) != ext.nonce {
 						eprintln!(
 							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: nonce mismatch",
 							block.header.block_number, i
 						);
-						continue;
-					}
+						crate::support::ExtrinsicOutcome::BadNonce
+					} else {
+						self.system.inc_nonce(&ext.signer);
+						let origin = crate::support::RuntimeOrigin::Signed(ext.signer);
+						let result = self.dispatch(origin, ext.call);
+						crate::support::RuntimeHooks::after_dispatch(self);
+						match result {
+							Ok(()) => crate::support::ExtrinsicOutcome::Applied,
+							Err(e) => {
+								eprintln!(
+									"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
+									block.header.block_number, i, e
+								);
+								crate::support::ExtrinsicOutcome::DispatchFailed(e.to_string())
+							}
+						}
+					};
 
-					self.system.inc_nonce(&ext.signer);
-					let _res = self.dispatch(ext.signer, ext.call).map_err(|e| {
-						eprintln!(
-							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
-							block.header.block_number, i, e
-						)
+					crate::support::record_extrinsic(&hash, &crate::support::ExtrinsicRecord {
+						block_number: block.header.block_number,
+						index: i as u32,
+						outcome,
+						extrinsic: encoded,
 					});
 				}
-				Ok(())
+				let state_root = crate::support::RuntimeHooks::state_root(self);
+				self.system.set_state_root(state_root);
+				Ok(state_root)
 			}
 		}
 	};
@@ -79,22 +207,22 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
-		#[derive(parity_scale_codec::Encode, parity_scale_codec::Decode)]
+		#[derive(Debug, parity_scale_codec::Encode, parity_scale_codec::Decode)]
 		pub enum RuntimeCall {
 			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
 		}
 
 		impl crate::support::Dispatch for #runtime_struct {
-			type Caller = <Runtime as system::Config>::AccountId;
+			type Origin = system::OriginFor<#runtime_struct>;
 			type Call = RuntimeCall;
-			// Dispatch a call on behalf of a caller. Increments the caller's nonce.
+			// Dispatch a call as `origin`.
 			//
 			// Dispatch allows us to identify which underlying pallet call we want to execute.
-			// Note that we extract the `caller` from the extrinsic, and use that information
-			// to determine who we are executing the call on behalf of.
+			// `execute_block` dispatches each extrinsic as `RuntimeOrigin::Signed` by its signer;
+			// runtime code may dispatch as `Root` or `None`.
 			fn dispatch(
 				&mut self,
-				caller: Self::Caller,
+				origin: Self::Origin,
 				runtime_call: Self::Call,
 			) -> crate::support::DispatchResult {
 				// This match statement will allow us to correctly route `RuntimeCall`s
@@ -102,7 +230,8 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 				match runtime_call {
 					#(
 						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
+							#pallet_dispatch
+							#root_dispatch
 						}
 					),*
 				}
@@ -6,12 +6,22 @@ pub fn runtime(
 	_attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	// The final expanded code will be placed here.
-	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
 	let item_mod = syn::parse_macro_input!(item as syn::Item);
 
+	// The final expanded code will be placed here. It starts with the `Runtime` struct itself,
+	// minus our own `#[context(...)]` and `#[dispatches_as_root]` field attributes, which the
+	// compiler would not recognize.
+	let mut output = item_mod.clone();
+	if let syn::Item::Struct(item_struct) = &mut output {
+		for field in item_struct.fields.iter_mut() {
+			field.attrs.retain(|attr| {
+				!attr.path().is_ident(parse::CONTEXT_ATTR) &&
+					!attr.path().is_ident(parse::DISPATCHES_AS_ROOT_ATTR)
+			});
+		}
+	}
+	let mut finished: proc_macro::TokenStream = quote::quote!(#output).into();
+
 	// First we parse the `Runtime` struct...
 	let generated: proc_macro::TokenStream = match parse::RuntimeDef::try_from(item_mod.clone()) {
 		// ..then we generate our new code.
@@ -8,8 +8,18 @@ pub struct RuntimeDef {
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
 	pub pallets: Vec<(syn::Ident, syn::Type)>,
+	/// For each pallet in `pallets`, the field named by its `#[context(field)]` attribute: the
+	/// pallet handed to its calls as context.
+	pub contexts: Vec<Option<syn::Ident>>,
+	/// For each pallet in `pallets`, whether it is marked `#[dispatches_as_root]`.
+	pub root_dispatchers: Vec<bool>,
 }
 
+/// Name of the field attribute that gives a pallet's calls another pallet as context.
+pub const CONTEXT_ATTR: &str = "context";
+/// Name of the field attribute for a pallet whose calls hand back calls to dispatch as root.
+pub const DISPATCHES_AS_ROOT_ATTR: &str = "dispatches_as_root";
+
 impl RuntimeDef {
 	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
 		// First we check that we are parsing a `struct`.
@@ -26,15 +36,54 @@ impl RuntimeDef {
 
 		// Here is where we will store a list of all the pallets.
 		let mut pallets = vec![];
+		let mut contexts = vec![];
+		let mut root_dispatchers = vec![];
 		// We skip `system`, which we ensure is the first field in `check_system`.
-		for field in item_struct.fields.into_iter().skip(1) {
-			if let Some(ident) = field.ident {
-				pallets.push((ident, field.ty))
+		for field in item_struct.fields.iter().skip(1) {
+			if let Some(ident) = &field.ident {
+				pallets.push((ident.clone(), field.ty.clone()));
+				contexts.push(parse_context(field)?);
+				root_dispatchers.push(parse_dispatches_as_root(field)?);
+			}
+		}
+
+		// A context must be another pallet of this runtime.
+		for (context, (name, _)) in contexts.iter().zip(&pallets) {
+			if let Some(context) = context {
+				if context == name || !pallets.iter().any(|(other, _)| other == context) {
+					let msg = "context must name another pallet field of the runtime";
+					return Err(syn::Error::new(context.span(), msg))
+				}
 			}
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		Ok(Self { runtime_struct, pallets, contexts, root_dispatchers })
+	}
+}
+
+/// Read the optional `#[dispatches_as_root]` attribute of a pallet field.
+fn parse_dispatches_as_root(field: &syn::Field) -> syn::Result<bool> {
+	let mut found = false;
+	for attr in field.attrs.iter().filter(|attr| attr.path().is_ident(DISPATCHES_AS_ROOT_ATTR)) {
+		if found {
+			return Err(syn::Error::new(attr.span(), "duplicate dispatches_as_root attribute"))
+		}
+		attr.meta.require_path_only()?;
+		found = true;
+	}
+	Ok(found)
+}
+
+/// Read the optional `#[context(field)]` attribute of a pallet field.
+fn parse_context(field: &syn::Field) -> syn::Result<Option<syn::Ident>> {
+	let mut context = None;
+	for attr in field.attrs.iter().filter(|attr| attr.path().is_ident(CONTEXT_ATTR)) {
+		if context.is_some() {
+			return Err(syn::Error::new(attr.span(), "duplicate context attribute"))
+		}
+		context = Some(attr.parse_args::<syn::Ident>()?);
 	}
+	Ok(context)
 }
 
 /// This function checks that the `system` pallet is the first pallet included in the `Runtime`
@@ -0,0 +1,342 @@
+use core::fmt::Debug;
+use num::traits::{CheckedAdd, CheckedSub, Zero};
+use parity_scale_codec::{Decode, Encode};
+use std::collections::BTreeMap;
+
+use crate::{
+	support::{DispatchResult, ensure_signed, load_prefix, write_prefix},
+	system::{self, OriginFor},
+};
+
+// One prefix per stored type; keys are the prefix followed by the SCALE-encoded map key.
+const PREFIX_DETAILS: &[u8] = b"assets:details:";
+const PREFIX_METADATA: &[u8] = b"assets:metadata:";
+const PREFIX_ACCOUNT: &[u8] = b"assets:account:";
+const PREFIX_APPROVAL: &[u8] = b"assets:approval:";
+
+pub trait Config: system::Config {
+	type AssetId: Debug + Ord + Copy + Encode + Decode;
+	type Balance: Zero + CheckedAdd + CheckedSub + Copy + Ord + Encode + Decode;
+	/// Longest asset name or symbol, in bytes.
+	const STRING_LIMIT: usize;
+}
+
+/// Who runs an asset and how much of it exists.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct AssetDetails<AccountId, Balance> {
+	/// The creator; the only account allowed to mint.
+	pub admin: AccountId,
+	pub supply: Balance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct AssetMetadata {
+	pub name: String,
+	pub symbol: String,
+	pub decimals: u8,
+}
+
+/// `(asset, owner, delegate)`.
+type ApprovalKey<AssetId, AccountId> = (AssetId, AccountId, AccountId);
+
+#[derive(Clone, Debug)]
+pub struct Pallet<T: Config> {
+	details: BTreeMap<T::AssetId, AssetDetails<T::AccountId, T::Balance>>,
+	metadata: BTreeMap<T::AssetId, AssetMetadata>,
+	accounts: BTreeMap<(T::AssetId, T::AccountId), T::Balance>,
+	approvals: BTreeMap<ApprovalKey<T::AssetId, T::AccountId>, T::Balance>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn new() -> Self {
+		Self {
+			details: load_prefix(PREFIX_DETAILS),
+			metadata: load_prefix(PREFIX_METADATA),
+			accounts: load_prefix(PREFIX_ACCOUNT),
+			approvals: load_prefix(PREFIX_APPROVAL),
+		}
+	}
+
+	pub fn details(&self, id: T::AssetId) -> Option<&AssetDetails<T::AccountId, T::Balance>> {
+		self.details.get(&id)
+	}
+
+	pub fn metadata(&self, id: T::AssetId) -> Option<&AssetMetadata> {
+		self.metadata.get(&id)
+	}
+
+	pub fn total_supply(&self, id: T::AssetId) -> T::Balance {
+		self.details.get(&id).map_or_else(T::Balance::zero, |details| details.supply)
+	}
+
+	pub fn balance(&self, id: T::AssetId, who: &T::AccountId) -> T::Balance {
+		let key = (id, who.clone());
+		*self.accounts.get(&key).unwrap_or(&T::Balance::zero())
+	}
+
+	/// How much `delegate` may still move out of `owner`'s `id` balance.
+	pub fn allowance(
+		&self,
+		id: T::AssetId,
+		owner: &T::AccountId,
+		delegate: &T::AccountId,
+	) -> T::Balance {
+		let key = (id, owner.clone(), delegate.clone());
+		*self.approvals.get(&key).unwrap_or(&T::Balance::zero())
+	}
+
+	fn write_balance(&mut self, id: T::AssetId, who: &T::AccountId, amount: T::Balance) {
+		let amount = (!amount.is_zero()).then_some(amount);
+		write_prefix(&mut self.accounts, PREFIX_ACCOUNT, (id, who.clone()), amount);
+	}
+
+	fn write_allowance(
+		&mut self,
+		id: T::AssetId,
+		owner: &T::AccountId,
+		delegate: &T::AccountId,
+		amount: T::Balance,
+	) {
+		let key = (id, owner.clone(), delegate.clone());
+		let amount = (!amount.is_zero()).then_some(amount);
+		write_prefix(&mut self.approvals, PREFIX_APPROVAL, key, amount);
+	}
+
+	fn do_transfer(
+		&mut self,
+		id: T::AssetId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		if !self.details.contains_key(&id) {
+			return Err("Unknown asset.");
+		}
+		let new_from = self.balance(id, from).checked_sub(&amount).ok_or("Not enough funds.")?;
+		if from == to {
+			return Ok(());
+		}
+		let new_to = self.balance(id, to).checked_add(&amount).ok_or("Overflow")?;
+		self.write_balance(id, from, new_from);
+		self.write_balance(id, to, new_to);
+		Ok(())
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create asset `id` with the caller as its admin and no supply.
+	pub fn create(
+		&mut self,
+		origin: OriginFor<T>,
+		id: T::AssetId,
+		name: String,
+		symbol: String,
+		decimals: u8,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if self.details.contains_key(&id) {
+			return Err("Asset id is already in use.");
+		}
+		if name.is_empty() || symbol.is_empty() {
+			return Err("Asset name and symbol must not be empty.");
+		}
+		if name.len() > T::STRING_LIMIT || symbol.len() > T::STRING_LIMIT {
+			return Err("Asset name or symbol is too long.");
+		}
+
+		let details = AssetDetails { admin: caller, supply: T::Balance::zero() };
+		write_prefix(&mut self.details, PREFIX_DETAILS, id, Some(details));
+		let metadata = AssetMetadata { name, symbol, decimals };
+		write_prefix(&mut self.metadata, PREFIX_METADATA, id, Some(metadata));
+		Ok(())
+	}
+
+	/// Admin-only: create `amount` of `id` in `beneficiary`'s account.
+	pub fn mint(
+		&mut self,
+		origin: OriginFor<T>,
+		id: T::AssetId,
+		beneficiary: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut details = self.details.get(&id).ok_or("Unknown asset.")?.clone();
+		if details.admin != caller {
+			return Err("Caller is not the asset admin.");
+		}
+		details.supply = details.supply.checked_add(&amount).ok_or("Supply overflow.")?;
+		let new_balance = self.balance(id, &beneficiary).checked_add(&amount).ok_or("Overflow")?;
+
+		write_prefix(&mut self.details, PREFIX_DETAILS, id, Some(details));
+		self.write_balance(id, &beneficiary, new_balance);
+		Ok(())
+	}
+
+	pub fn transfer(
+		&mut self,
+		origin: OriginFor<T>,
+		id: T::AssetId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		self.do_transfer(id, &caller, &to, amount)
+	}
+
+	/// Let `delegate` move up to `amount` of the caller's `id` with `transfer_from`. Replaces any
+	/// earlier allowance; zero revokes it.
+	pub fn approve(
+		&mut self,
+		origin: OriginFor<T>,
+		id: T::AssetId,
+		delegate: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if !self.details.contains_key(&id) {
+			return Err("Unknown asset.");
+		}
+		self.write_allowance(id, &caller, &delegate, amount);
+		Ok(())
+	}
+
+	/// Move `amount` of `owner`'s `id` to `to`, spending the caller's allowance.
+	pub fn transfer_from(
+		&mut self,
+		origin: OriginFor<T>,
+		id: T::AssetId,
+		owner: T::AccountId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let remaining = self
+			.allowance(id, &owner, &caller)
+			.checked_sub(&amount)
+			.ok_or("Amount exceeds the allowance.")?;
+		self.do_transfer(id, &owner, &to, amount)?;
+		self.write_allowance(id, &owner, &caller, remaining);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::support::RuntimeOrigin;
+
+	struct TestConfig;
+	impl system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+	}
+	impl Config for TestConfig {
+		type AssetId = u32;
+		type Balance = u128;
+		const STRING_LIMIT: usize = 8;
+	}
+
+	/// A pallet with asset 1 ("Gold", 2 decimals) created by alice, who holds 1_000 of it.
+	fn with_gold() -> Pallet<TestConfig> {
+		let mut p = Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		p.create(RuntimeOrigin::Signed(alice.clone()), 1, "Gold".into(), "GLD".into(), 2)
+			.unwrap();
+		p.mint(RuntimeOrigin::Signed(alice.clone()), 1, alice, 1_000).unwrap();
+		p
+	}
+
+	#[test]
+	fn create_records_admin_and_metadata() {
+		let p = with_gold();
+		let details = p.details(1).unwrap();
+		assert_eq!(details.admin, "alice");
+		assert_eq!(p.total_supply(1), 1_000);
+		let metadata = AssetMetadata { name: "Gold".into(), symbol: "GLD".into(), decimals: 2 };
+		assert_eq!(p.metadata(1), Some(&metadata));
+		assert_eq!(Pallet::<TestConfig>::new().metadata(1), Some(&metadata), "persisted");
+	}
+
+	#[test]
+	fn create_rejects_taken_ids_and_bad_metadata() {
+		let mut p = with_gold();
+		let bob = "bob".to_string();
+		let err = p.create(RuntimeOrigin::Signed(bob.clone()), 1, "Silver".into(), "SLV".into(), 0);
+		assert_eq!(err, Err("Asset id is already in use."));
+		assert!(
+			p.create(RuntimeOrigin::Signed(bob.clone()), 2, "".into(), "SLV".into(), 0)
+				.is_err()
+		);
+		assert!(
+			p.create(RuntimeOrigin::Signed(bob), 2, "Silverware".into(), "SLV".into(), 0)
+				.is_err()
+		);
+		assert!(p.details(2).is_none());
+	}
+
+	#[test]
+	fn only_the_admin_can_mint() {
+		let mut p = with_gold();
+		let bob = "bob".to_string();
+		assert_eq!(
+			p.mint(RuntimeOrigin::Signed(bob.clone()), 1, bob.clone(), 5),
+			Err("Caller is not the asset admin.")
+		);
+		assert_eq!(
+			p.mint(RuntimeOrigin::Signed(bob.clone()), 9, bob.clone(), 5),
+			Err("Unknown asset.")
+		);
+		p.mint(RuntimeOrigin::Signed("alice".to_string()), 1, bob.clone(), 5).unwrap();
+		assert_eq!((p.balance(1, &bob), p.total_supply(1)), (5, 1_005));
+	}
+
+	#[test]
+	fn transfer_moves_balances_but_not_supply() {
+		let mut p = with_gold();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed(alice.clone()), 1, bob.clone(), 1_001),
+			Err("Not enough funds.")
+		);
+		p.transfer(RuntimeOrigin::Signed(alice.clone()), 1, bob.clone(), 1_000).unwrap();
+		assert_eq!((p.balance(1, &alice), p.balance(1, &bob)), (0, 1_000));
+		assert_eq!(p.total_supply(1), 1_000);
+		assert!(!p.accounts.contains_key(&(1, alice)), "empty balances are removed");
+	}
+
+	#[test]
+	fn transfer_from_spends_the_allowance() {
+		let mut p = with_gold();
+		let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+		p.approve(RuntimeOrigin::Signed(alice.clone()), 1, bob.clone(), 300).unwrap();
+		p.transfer_from(RuntimeOrigin::Signed(bob.clone()), 1, alice.clone(), carol.clone(), 200)
+			.unwrap();
+		assert_eq!(p.allowance(1, &alice, &bob), 100);
+		assert_eq!(
+			p.transfer_from(
+				RuntimeOrigin::Signed(bob.clone()),
+				1,
+				alice.clone(),
+				carol.clone(),
+				101
+			),
+			Err("Amount exceeds the allowance.")
+		);
+		assert!(
+			p.transfer_from(
+				RuntimeOrigin::Signed(carol.clone()),
+				1,
+				alice.clone(),
+				carol.clone(),
+				1
+			)
+			.is_err()
+		);
+
+		p.approve(RuntimeOrigin::Signed(alice.clone()), 1, bob.clone(), 0).unwrap();
+		assert_eq!(p.allowance(1, &alice, &bob), 0);
+		assert_eq!((p.balance(1, &alice), p.balance(1, &carol)), (800, 200));
+	}
+}
@@ -1,82 +1,548 @@
-use num::traits::{CheckedAdd, CheckedSub, Zero};
+use num::traits::{Bounded, CheckedAdd, CheckedSub, Zero};
 use parity_scale_codec::{Decode, Encode};
 use std::collections::BTreeMap;
 
+pub use crate::support::{BalanceStatus, ExistenceRequirement, LockIdentifier};
 use crate::{
-	support::{KeyValueStore, kv_store},
-	system,
+	support::{
+		Currency, DispatchResult, KeyValueStore, ensure_root, ensure_signed, kv_store, load_prefix,
+	},
+	system::{self, OriginFor},
 };
 
 const PREFIX_BALANCE: &[u8] = b"balances:";
+// Outside `PREFIX_BALANCE`, so the balance scan never sees them.
+const PREFIX_RESERVED: &[u8] = b"balances_reserved:";
+const PREFIX_LOCKS: &[u8] = b"balances_locks:";
+const KEY_TOTAL_ISSUANCE: &[u8] = b"balances_total_issuance";
+
+/// What happens to the dust, the balance below the existential deposit left in a reaped account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DustHandling<AccountId> {
+	Burn,
+	MoveTo(AccountId),
+}
+
+/// A named lock freezing `amount` of an account's free balance, up to block `until` if set.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct BalanceLock<Balance, BlockNumber> {
+	pub id: LockIdentifier,
+	pub amount: Balance,
+	pub until: Option<BlockNumber>,
+}
+
+type LockOf<T> = BalanceLock<<T as Config>::Balance, <T as system::Config>::BlockNumber>;
 
 pub trait Config: system::Config {
-	type Balance: Zero + CheckedSub + CheckedAdd + Copy + Encode + Decode;
+	type Balance: Zero + CheckedSub + CheckedAdd + Bounded + Copy + Ord + Encode + Decode;
+	/// Smallest total (free plus reserved) balance an account may hold. An account that drops
+	/// below it is reaped: its balances, locks and `system` nonce storage are deleted.
+	const EXISTENTIAL_DEPOSIT: Self::Balance;
+
+	fn dust_handling() -> DustHandling<Self::AccountId> {
+		DustHandling::Burn
+	}
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Pallet<T: Config> {
+	/// Free balances.
 	balances: BTreeMap<T::AccountId, T::Balance>,
+	reserved: BTreeMap<T::AccountId, T::Balance>,
+	locks: BTreeMap<T::AccountId, Vec<LockOf<T>>>,
+	/// Sum of all free and reserved balances. Only `mint`, `burn`, `set_balance`,
+	/// `slash_reserved` and burned dust change it.
+	total_issuance: T::Balance,
+	/// Current block, set by `on_initialize`, against which lock expiry is checked.
+	now: T::BlockNumber,
+	/// Accounts reaped since the last `take_reaped`, for the runtime to clear in `system`.
+	reaped: Vec<T::AccountId>,
 }
 
 impl<T: Config> Pallet<T> {
 	pub fn new() -> Self {
 		let store = kv_store();
-		let mut balances = BTreeMap::new();
+		let balances: BTreeMap<T::AccountId, T::Balance> = load_prefix(PREFIX_BALANCE);
+		let reserved: BTreeMap<T::AccountId, T::Balance> = load_prefix(PREFIX_RESERVED);
 
-		for (key, value) in store.scan_prefix(PREFIX_BALANCE) {
-			if key.len() <= PREFIX_BALANCE.len() {
-				continue;
-			}
-			let account_bytes = &key[PREFIX_BALANCE.len()..];
-			if let (Ok(account), Ok(balance)) =
-				(T::AccountId::decode(&mut &account_bytes[..]), T::Balance::decode(&mut &value[..]))
-			{
-				balances.insert(account, balance);
-			}
-		}
+		// A store written before issuance was tracked starts from the sum of its balances.
+		let total_issuance = match store.get(KEY_TOTAL_ISSUANCE) {
+			Some(bytes) =>
+				T::Balance::decode(&mut &bytes[..]).unwrap_or_else(|_| T::Balance::zero()),
+			None => balances
+				.values()
+				.chain(reserved.values())
+				.try_fold(T::Balance::zero(), |sum, b| sum.checked_add(b))
+				.expect("total issuance overflow"),
+		};
 
-		Self { balances }
+		Self {
+			balances,
+			reserved,
+			locks: load_prefix(PREFIX_LOCKS),
+			total_issuance,
+			now: T::BlockNumber::zero(),
+			reaped: Vec::new(),
+		}
 	}
 
-	fn balance_key(who: &T::AccountId) -> Vec<u8> {
-		let mut key = PREFIX_BALANCE.to_vec();
+	fn account_key(prefix: &[u8], who: &T::AccountId) -> Vec<u8> {
+		let mut key = prefix.to_vec();
 		key.extend(who.encode());
 		key
 	}
 
+	fn balance_key(who: &T::AccountId) -> Vec<u8> {
+		Self::account_key(PREFIX_BALANCE, who)
+	}
+
+	/// Called by the runtime at the start of every block.
+	pub fn on_initialize(&mut self, now: T::BlockNumber) {
+		self.now = now;
+	}
+
+	/// Force a free balance, ignoring the existential deposit and locks. The difference is minted
+	/// or burned, so total issuance stays consistent.
 	pub fn set_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
-		self.balances.insert(who.clone(), amount);
+		let old = self.balance(who);
+		let issuance = if amount >= old {
+			self.total_issuance
+				.checked_add(&(amount - old))
+				.expect("total issuance overflow")
+		} else {
+			out_of_step(self.total_issuance.checked_sub(&(old - amount)), T::Balance::zero())
+		};
+		self.set_total_issuance(issuance);
+		self.write_balance(who, amount);
+	}
 
+	/// Store `amount` as `who`'s free balance. Zero removes the entry. Leaves issuance alone.
+	fn write_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
 		let key = Self::balance_key(who);
-		let encoded = amount.encode();
-		if let Err(e) = kv_store().put(&key, &encoded) {
-			eprintln!("Failed to persist balance: {e}");
-		}
+		write_entry(&mut self.balances, &key, who, amount, amount.is_zero(), "balance");
+	}
+
+	fn write_reserved(&mut self, who: &T::AccountId, amount: T::Balance) {
+		let key = Self::account_key(PREFIX_RESERVED, who);
+		write_entry(&mut self.reserved, &key, who, amount, amount.is_zero(), "reserved balance");
+	}
+
+	fn write_locks(&mut self, who: &T::AccountId, locks: Vec<LockOf<T>>) {
+		let key = Self::account_key(PREFIX_LOCKS, who);
+		let empty = locks.is_empty();
+		write_entry(&mut self.locks, &key, who, locks, empty, "locks");
 	}
 
+	/// Free balance.
 	pub fn balance(&self, who: &T::AccountId) -> T::Balance {
 		*self.balances.get(who).unwrap_or(&T::Balance::zero())
 	}
+
+	pub fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+		*self.reserved.get(who).unwrap_or(&T::Balance::zero())
+	}
+
+	/// Free plus reserved balance; what the existential deposit is checked against.
+	pub fn total_balance(&self, who: &T::AccountId) -> T::Balance {
+		let total = self.balance(who).checked_add(&self.reserved_balance(who));
+		out_of_step(total, T::Balance::max_value())
+	}
+
+	/// Locks on `who`, including any that have expired but not yet been cleared.
+	pub fn locks(&self, who: &T::AccountId) -> &[LockOf<T>] {
+		self.locks.get(who).map_or(&[], Vec::as_slice)
+	}
+
+	/// Part of the free balance frozen by locks: the largest lock still in force.
+	pub fn frozen_balance(&self, who: &T::AccountId) -> T::Balance {
+		self.locks(who)
+			.iter()
+			.filter(|lock| self.lock_applies(lock))
+			.map(|lock| lock.amount)
+			.max()
+			.unwrap_or_else(T::Balance::zero)
+	}
+
+	/// Free balance not frozen by locks.
+	pub fn usable_balance(&self, who: &T::AccountId) -> T::Balance {
+		self.balance(who)
+			.checked_sub(&self.frozen_balance(who))
+			.unwrap_or_else(T::Balance::zero)
+	}
+
+	fn lock_applies(&self, lock: &LockOf<T>) -> bool {
+		lock.until.is_none_or(|until| self.now < until)
+	}
+
+	/// Withdrawals may not take the free balance below what locks freeze.
+	fn ensure_can_withdraw(&self, who: &T::AccountId, new_balance: T::Balance) -> DispatchResult {
+		if new_balance < self.frozen_balance(who) {
+			return Err("Balance is locked.");
+		}
+		Ok(())
+	}
+
+	/// Free balance `who` can withdraw: what locks leave, less whatever must stay behind to
+	/// keep the account alive under `keep_alive`.
+	pub fn reducible_balance(&self, who: &T::AccountId, keep_alive: bool) -> T::Balance {
+		let mut keep = self.frozen_balance(who);
+		if keep_alive {
+			let reserved = self.reserved_balance(who);
+			if reserved < T::EXISTENTIAL_DEPOSIT {
+				keep = keep.max(T::EXISTENTIAL_DEPOSIT - reserved);
+			}
+		}
+		self.balance(who).checked_sub(&keep).unwrap_or_else(T::Balance::zero)
+	}
+
+	/// Whether `who` would be reaped holding `free` alongside its current reserve.
+	fn below_existential_deposit(&self, who: &T::AccountId, free: T::Balance) -> bool {
+		free.checked_add(&self.reserved_balance(who))
+			.is_none_or(|total| total < T::EXISTENTIAL_DEPOSIT)
+	}
+
+	pub fn total_issuance(&self) -> T::Balance {
+		self.total_issuance
+	}
+
+	fn set_total_issuance(&mut self, amount: T::Balance) {
+		self.total_issuance = amount;
+		if let Err(e) = kv_store().put(KEY_TOTAL_ISSUANCE, &amount.encode()) {
+			eprintln!("Failed to persist total issuance: {e}");
+		}
+	}
+
+	/// Create `amount` new tokens in `who`'s account, which must end up holding at least the
+	/// existential deposit.
+	pub fn mint(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+		let issuance =
+			self.total_issuance.checked_add(&amount).ok_or("Total issuance overflow.")?;
+		let new_balance = self.balance(who).checked_add(&amount).ok_or("Overflow")?;
+		if self.below_existential_deposit(who, new_balance) {
+			return Err("Balance would be below the existential deposit.");
+		}
+		self.set_total_issuance(issuance);
+		self.write_balance(who, new_balance);
+		Ok(())
+	}
+
+	/// Destroy `amount` of `who`'s free tokens. Leaving it below the existential deposit fails
+	/// under `KeepAlive` and reaps it under `AllowDeath`.
+	pub fn burn(
+		&mut self,
+		who: &T::AccountId,
+		amount: T::Balance,
+		existence: ExistenceRequirement,
+	) -> DispatchResult {
+		let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds.")?;
+		self.ensure_can_withdraw(who, new_balance)?;
+		let reap = self.below_existential_deposit(who, new_balance);
+		if reap && existence == ExistenceRequirement::KeepAlive {
+			return Err("Burn would reap the account.");
+		}
+		let issuance =
+			self.total_issuance.checked_sub(&amount).ok_or("Total issuance underflow.")?;
+		self.set_total_issuance(issuance);
+		if reap {
+			self.reap(who, new_balance);
+		} else {
+			self.write_balance(who, new_balance);
+		}
+		Ok(())
+	}
+
+	/// Invariant: total issuance equals the sum of all free and reserved balances.
+	pub fn try_state(&self) -> DispatchResult {
+		let sum = self
+			.balances
+			.values()
+			.chain(self.reserved.values())
+			.try_fold(T::Balance::zero(), |sum, b| sum.checked_add(b))
+			.ok_or("sum of balances overflows")?;
+		if sum != self.total_issuance {
+			return Err("total issuance does not match the sum of balances");
+		}
+		Ok(())
+	}
+
+	/// Move `amount` from `from` to `to`. The recipient must end up with at least the existential
+	/// deposit, and the sender may not dip into locked funds. A sender left below the deposit
+	/// fails the transfer under `KeepAlive`, and is reaped under `AllowDeath`, its dust handled
+	/// per `Config::dust_handling`.
+	pub fn do_transfer(
+		&mut self,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+		existence: ExistenceRequirement,
+	) -> DispatchResult {
+		let new_from_balance =
+			self.balance(from).checked_sub(&amount).ok_or("Not enough funds.")?;
+		if from == to {
+			return Ok(());
+		}
+		self.ensure_can_withdraw(from, new_from_balance)?;
+		let new_to_balance = self.balance(to).checked_add(&amount).ok_or("Overflow")?;
+		if self.below_existential_deposit(to, new_to_balance) {
+			return Err("Recipient balance would be below the existential deposit.");
+		}
+		let reap = self.below_existential_deposit(from, new_from_balance);
+		if reap && existence == ExistenceRequirement::KeepAlive {
+			return Err("Transfer would reap the sender.");
+		}
+
+		self.write_balance(to, new_to_balance);
+		if reap {
+			self.reap(from, new_from_balance);
+		} else {
+			self.write_balance(from, new_from_balance);
+		}
+		Ok(())
+	}
+
+	/// Delete `who`'s balances and locks, queue it for `take_reaped`, and hand the dust (`free`
+	/// plus whatever is still reserved) to `Config::dust_handling`. Dust the destination cannot
+	/// hold is burned.
+	fn reap(&mut self, who: &T::AccountId, free: T::Balance) {
+		let dust =
+			out_of_step(free.checked_add(&self.reserved_balance(who)), T::Balance::max_value());
+		self.write_balance(who, T::Balance::zero());
+		self.write_reserved(who, T::Balance::zero());
+		self.write_locks(who, Vec::new());
+		self.reaped.push(who.clone());
+		if dust.is_zero() {
+			return;
+		}
+		if let DustHandling::MoveTo(dest) = T::dust_handling() {
+			if let Some(credited) = self.balance(&dest).checked_add(&dust) {
+				self.write_balance(&dest, credited);
+				return;
+			}
+			eprintln!("Dust of a reaped account overflows its destination; burning it");
+		}
+		let issuance = self.total_issuance.checked_sub(&dust);
+		self.set_total_issuance(out_of_step(issuance, T::Balance::zero()));
+	}
+
+	/// Accounts reaped since the last call. The runtime clears their `system` state.
+	pub fn take_reaped(&mut self) -> Vec<T::AccountId> {
+		std::mem::take(&mut self.reaped)
+	}
+}
+
+impl<T: Config> Currency<T::AccountId> for Pallet<T> {
+	type Balance = T::Balance;
+	type BlockNumber = T::BlockNumber;
+
+	fn free_balance(&self, who: &T::AccountId) -> T::Balance {
+		self.balance(who)
+	}
+
+	fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+		Pallet::reserved_balance(self, who)
+	}
+
+	fn transfer(
+		&mut self,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+		existence: ExistenceRequirement,
+	) -> DispatchResult {
+		self.do_transfer(from, to, amount, existence)
+	}
+
+	fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+		let new_balance = self.balance(who).checked_sub(&amount).ok_or("Not enough funds.")?;
+		self.ensure_can_withdraw(who, new_balance)?;
+		let new_reserved = self.reserved_balance(who).checked_add(&amount).ok_or("Overflow")?;
+		self.write_balance(who, new_balance);
+		self.write_reserved(who, new_reserved);
+		Ok(())
+	}
+
+	fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+		let reserved = self.reserved_balance(who);
+		let actual = amount.min(reserved);
+		let new_balance =
+			out_of_step(self.balance(who).checked_add(&actual), T::Balance::max_value());
+		self.write_reserved(who, reserved - actual);
+		self.write_balance(who, new_balance);
+		amount - actual
+	}
+
+	fn slash_reserved(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+		let reserved = self.reserved_balance(who);
+		let actual = amount.min(reserved);
+		if actual.is_zero() {
+			return amount;
+		}
+		let issuance = self.total_issuance.checked_sub(&actual);
+		self.set_total_issuance(out_of_step(issuance, T::Balance::zero()));
+		self.write_reserved(who, reserved - actual);
+		let free = self.balance(who);
+		if self.below_existential_deposit(who, free) {
+			self.reap(who, free);
+		}
+		amount - actual
+	}
+
+	fn repatriate_reserved(
+		&mut self,
+		slashed: &T::AccountId,
+		beneficiary: &T::AccountId,
+		amount: T::Balance,
+		status: BalanceStatus,
+	) -> Result<T::Balance, &'static str> {
+		let reserved = self.reserved_balance(slashed);
+		if slashed == beneficiary {
+			return Ok(match status {
+				BalanceStatus::Free => self.unreserve(slashed, amount),
+				BalanceStatus::Reserved => amount - amount.min(reserved),
+			});
+		}
+
+		let actual = amount.min(reserved);
+		let (mut free, mut held) = (self.balance(beneficiary), self.reserved_balance(beneficiary));
+		match status {
+			BalanceStatus::Free => free = free.checked_add(&actual).ok_or("Overflow")?,
+			BalanceStatus::Reserved => held = held.checked_add(&actual).ok_or("Overflow")?,
+		}
+		if free.checked_add(&held).is_none_or(|total| total < T::EXISTENTIAL_DEPOSIT) {
+			return Err("Recipient balance would be below the existential deposit.");
+		}
+
+		self.write_balance(beneficiary, free);
+		self.write_reserved(beneficiary, held);
+		self.write_reserved(slashed, reserved - actual);
+		let slashed_free = self.balance(slashed);
+		if !actual.is_zero() && self.below_existential_deposit(slashed, slashed_free) {
+			self.reap(slashed, slashed_free);
+		}
+		Ok(amount - actual)
+	}
+
+	fn set_lock(
+		&mut self,
+		id: LockIdentifier,
+		who: &T::AccountId,
+		amount: T::Balance,
+		until: Option<T::BlockNumber>,
+	) {
+		let mut locks = self.locks.get(who).cloned().unwrap_or_default();
+		// Expired locks are only cleared when the account's locks are next written.
+		locks.retain(|lock| lock.id != id && self.lock_applies(lock));
+		locks.push(BalanceLock { id, amount, until });
+		self.write_locks(who, locks);
+	}
+
+	fn remove_lock(&mut self, id: LockIdentifier, who: &T::AccountId) {
+		let mut locks = self.locks.get(who).cloned().unwrap_or_default();
+		locks.retain(|lock| lock.id != id && self.lock_applies(lock));
+		self.write_locks(who, locks);
+	}
+}
+
+/// Issuance covers every balance, so arithmetic bounded by it only fails once balances and
+/// issuance are already out of step. Log that and clamp rather than panic mid-block.
+fn out_of_step<B>(value: Option<B>, clamped: B) -> B {
+	debug_assert!(value.is_some(), "total issuance out of step with balances");
+	value.unwrap_or_else(|| {
+		eprintln!("Total issuance out of step with balances; clamping");
+		clamped
+	})
+}
+
+/// Mirror `value` into `map` and storage, or remove both when `remove` is set.
+fn write_entry<K: Ord + Clone, V: Encode>(
+	map: &mut BTreeMap<K, V>,
+	key: &[u8],
+	who: &K,
+	value: V,
+	remove: bool,
+	what: &str,
+) {
+	if remove {
+		map.remove(who);
+		if let Err(e) = kv_store().delete(key) {
+			eprintln!("Failed to delete {what}: {e}");
+		}
+		return;
+	}
+
+	if let Err(e) = kv_store().put(key, &value.encode()) {
+		eprintln!("Failed to persist {what}: {e}");
+	}
+	map.insert(who.clone(), value);
 }
 
 #[macros::call]
 impl<T: Config> Pallet<T> {
+	/// Transfer that may reap the sender, like Substrate's `transfer_allow_death`.
 	pub fn transfer(
 		&mut self,
-		caller: T::AccountId,
+		origin: OriginFor<T>,
 		to: T::AccountId,
 		amount: T::Balance,
-	) -> crate::support::DispatchResult {
-		let caller_balance = self.balance(&caller);
-		let to_balance = self.balance(&to);
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		self.do_transfer(&caller, &to, amount, ExistenceRequirement::AllowDeath)
+	}
 
-		let new_caller_balance = caller_balance.checked_sub(&amount).ok_or("Not enough funds.")?;
+	/// Transfer that fails rather than reap the sender.
+	pub fn transfer_keep_alive(
+		&mut self,
+		origin: OriginFor<T>,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		self.do_transfer(&caller, &to, amount, ExistenceRequirement::KeepAlive)
+	}
 
-		let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow")?;
+	/// Transfer everything the caller can withdraw. With `keep_alive`, leave enough behind to
+	/// keep the account alive; otherwise it is reaped unless locks or reserves hold it up.
+	pub fn transfer_all(
+		&mut self,
+		origin: OriginFor<T>,
+		to: T::AccountId,
+		keep_alive: bool,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let amount = self.reducible_balance(&caller, keep_alive);
+		let existence = if keep_alive {
+			ExistenceRequirement::KeepAlive
+		} else {
+			ExistenceRequirement::AllowDeath
+		};
+		self.do_transfer(&caller, &to, amount, existence)
+	}
 
-		self.set_balance(&caller, new_caller_balance);
-		self.set_balance(&to, new_to_balance);
+	/// Root-only transfer out of any account, which may reap it.
+	pub fn force_transfer(
+		&mut self,
+		origin: OriginFor<T>,
+		source: T::AccountId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		ensure_root(origin)?;
+		self.do_transfer(&source, &to, amount, ExistenceRequirement::AllowDeath)
+	}
 
+	/// Root-only: set `who`'s free balance, minting or burning the difference. A balance that
+	/// leaves the account below the existential deposit reaps it.
+	pub fn force_set_balance(
+		&mut self,
+		origin: OriginFor<T>,
+		who: T::AccountId,
+		new_free: T::Balance,
+	) -> DispatchResult {
+		ensure_root(origin)?;
+		self.set_balance(&who, new_free);
+		if !self.total_balance(&who).is_zero() && self.below_existential_deposit(&who, new_free) {
+			self.reap(&who, new_free);
+		}
 		Ok(())
 	}
 }
@@ -84,6 +550,7 @@ impl<T: Config> Pallet<T> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::support::RuntimeOrigin;
 	use crate::system;
 
 	struct TestConfig;
@@ -94,6 +561,23 @@ mod tests {
 	}
 	impl Config for TestConfig {
 		type Balance = u128;
+		const EXISTENTIAL_DEPOSIT: u128 = 10;
+	}
+
+	/// Like `TestConfig`, but dust goes to a treasury account instead of being burned.
+	struct DustToTreasury;
+	impl system::Config for DustToTreasury {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+	}
+	impl Config for DustToTreasury {
+		type Balance = u128;
+		const EXISTENTIAL_DEPOSIT: u128 = 10;
+
+		fn dust_handling() -> DustHandling<String> {
+			DustHandling::MoveTo("treasury".to_string())
+		}
 	}
 
 	fn new() -> Pallet<TestConfig> {
@@ -117,7 +601,10 @@ mod tests {
 	fn transfer_ok() {
 		let mut p = new();
 		p.set_balance(&"alice".to_string(), 100);
-		assert_eq!(p.transfer("alice".to_string(), "bob".to_string(), 40), Ok(()));
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 40),
+			Ok(())
+		);
 		assert_eq!(p.balance(&"alice".to_string()), 60);
 		assert_eq!(p.balance(&"bob".to_string()), 40);
 	}
@@ -126,7 +613,10 @@ mod tests {
 	fn transfer_exact_balance_empties_sender() {
 		let mut p = new();
 		p.set_balance(&"alice".to_string(), 100);
-		assert_eq!(p.transfer("alice".to_string(), "bob".to_string(), 100), Ok(()));
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 100),
+			Ok(())
+		);
 		assert_eq!(p.balance(&"alice".to_string()), 0);
 		assert_eq!(p.balance(&"bob".to_string()), 100);
 	}
@@ -135,7 +625,10 @@ mod tests {
 	fn transfer_insufficient_funds_leaves_balances_unchanged() {
 		let mut p = new();
 		p.set_balance(&"alice".to_string(), 50);
-		assert_eq!(p.transfer("alice".to_string(), "bob".to_string(), 51), Err("Not enough funds."));
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 51),
+			Err("Not enough funds.")
+		);
 		assert_eq!(p.balance(&"alice".to_string()), 50);
 		assert_eq!(p.balance(&"bob".to_string()), 0);
 	}
@@ -143,16 +636,288 @@ mod tests {
 	#[test]
 	fn transfer_from_zero_balance_fails() {
 		let mut p = new();
-		assert_eq!(p.transfer("alice".to_string(), "bob".to_string(), 1), Err("Not enough funds."));
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 1),
+			Err("Not enough funds.")
+		);
 	}
 
 	#[test]
 	fn multiple_transfers_accumulate() {
 		let mut p = new();
 		p.set_balance(&"alice".to_string(), 100);
-		p.transfer("alice".to_string(), "bob".to_string(), 30).unwrap();
-		p.transfer("alice".to_string(), "bob".to_string(), 30).unwrap();
+		p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 30)
+			.unwrap();
+		p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 30)
+			.unwrap();
 		assert_eq!(p.balance(&"alice".to_string()), 40);
 		assert_eq!(p.balance(&"bob".to_string()), 60);
 	}
+
+	#[test]
+	fn transfer_to_self_is_a_no_op() {
+		let mut p = new();
+		p.set_balance(&"alice".to_string(), 100);
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "alice".to_string(), 60),
+			Ok(())
+		);
+		assert_eq!(p.balance(&"alice".to_string()), 100);
+	}
+
+	#[test]
+	fn transfer_below_existential_deposit_to_new_account_fails() {
+		let mut p = new();
+		p.set_balance(&"alice".to_string(), 100);
+		assert!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 9)
+				.is_err()
+		);
+		assert_eq!(p.balance(&"alice".to_string()), 100);
+		p.set_balance(&"bob".to_string(), 10);
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 9),
+			Ok(())
+		);
+	}
+
+	#[test]
+	fn allow_death_reaps_sender_and_burns_dust() {
+		let mut p = new();
+		p.set_balance(&"alice".to_string(), 100);
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 95),
+			Ok(())
+		);
+		assert_eq!(p.balance(&"alice".to_string()), 0);
+		assert_eq!(p.balance(&"bob".to_string()), 95);
+		assert_eq!(p.take_reaped(), vec!["alice".to_string()]);
+		assert!(p.take_reaped().is_empty());
+		let key = Pallet::<TestConfig>::balance_key(&"alice".to_string());
+		assert!(kv_store().get(&key).is_none());
+	}
+
+	#[test]
+	fn keep_alive_refuses_to_reap_sender() {
+		let mut p = new();
+		p.set_balance(&"alice".to_string(), 100);
+		let keep_alive = ExistenceRequirement::KeepAlive;
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		assert!(p.do_transfer(&alice, &bob, 95, keep_alive).is_err());
+		assert!(p.do_transfer(&alice, &bob, 100, keep_alive).is_err());
+		assert_eq!(p.do_transfer(&alice, &bob, 90, keep_alive), Ok(()));
+		assert_eq!(p.balance(&alice), 10);
+		assert!(p.take_reaped().is_empty());
+	}
+
+	#[test]
+	fn dust_can_move_to_another_account() {
+		let mut p = Pallet::<DustToTreasury>::new();
+		p.set_balance(&"alice".to_string(), 100);
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed("alice".to_string()), "bob".to_string(), 95),
+			Ok(())
+		);
+		assert_eq!(p.balance(&"alice".to_string()), 0);
+		assert_eq!(p.balance(&"treasury".to_string()), 5);
+	}
+
+	#[test]
+	fn dust_that_overflows_its_destination_is_burned() {
+		let mut p = Pallet::<DustToTreasury>::new();
+		let (alice, treasury) = ("alice".to_string(), "treasury".to_string());
+		// Only reachable with issuance already out of step; written directly to get there.
+		p.write_balance(&treasury, u128::MAX);
+		p.set_balance(&alice, 100);
+		let issuance = p.total_issuance();
+		p.transfer(RuntimeOrigin::Signed(alice.clone()), "bob".to_string(), 95).unwrap();
+		assert_eq!(p.balance(&alice), 0);
+		assert_eq!(p.balance(&treasury), u128::MAX);
+		assert_eq!(p.total_issuance(), issuance - 5);
+	}
+
+	#[test]
+	fn mint_and_burn_track_total_issuance() {
+		let mut p = new();
+		let alice = "alice".to_string();
+		assert!(p.mint(&alice, 5).is_err(), "below the existential deposit");
+		p.mint(&alice, 100).unwrap();
+		p.mint(&alice, 5).unwrap();
+		assert_eq!(p.total_issuance(), 105);
+		p.burn(&alice, 50, ExistenceRequirement::KeepAlive).unwrap();
+		assert_eq!((p.balance(&alice), p.total_issuance()), (55, 55));
+		assert!(p.burn(&alice, 50, ExistenceRequirement::KeepAlive).is_err());
+		p.burn(&alice, 50, ExistenceRequirement::AllowDeath).unwrap();
+		assert_eq!((p.balance(&alice), p.total_issuance()), (0, 0));
+		assert_eq!(p.take_reaped(), vec![alice]);
+		assert_eq!(p.try_state(), Ok(()));
+	}
+
+	#[test]
+	fn total_issuance_survives_reload() {
+		let mut p = new();
+		p.mint(&"alice".to_string(), 100).unwrap();
+		p.set_balance(&"bob".to_string(), 40);
+		assert_eq!(new().total_issuance(), 140);
+		p.set_balance(&"bob".to_string(), 15);
+		assert_eq!(new().total_issuance(), 115);
+	}
+
+	#[test]
+	fn issuance_invariant_holds_across_operations() {
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let mut p = new();
+		p.set_balance(&alice, 100);
+		p.transfer(RuntimeOrigin::Signed(alice.clone()), bob.clone(), 95).unwrap();
+		assert_eq!(p.total_issuance(), 95, "burned dust leaves issuance");
+		assert_eq!(p.try_state(), Ok(()));
+
+		let mut t = Pallet::<DustToTreasury>::new();
+		let before = t.total_issuance();
+		t.mint(&alice, 100).unwrap();
+		t.transfer(RuntimeOrigin::Signed(alice), bob, 95).unwrap();
+		assert_eq!(t.total_issuance(), before + 100, "dust moved to treasury stays issued");
+		assert_eq!(t.try_state(), Ok(()));
+	}
+
+	#[test]
+	fn reserve_and_unreserve_move_between_free_and_reserved() {
+		let mut p = new();
+		let alice = "alice".to_string();
+		p.set_balance(&alice, 100);
+		assert_eq!(p.reserve(&alice, 101), Err("Not enough funds."));
+		assert_eq!(p.reserve(&alice, 60), Ok(()));
+		assert_eq!((p.balance(&alice), p.reserved_balance(&alice)), (40, 60));
+		assert_eq!(p.unreserve(&alice, 70), 10, "only 60 was reserved");
+		assert_eq!((p.balance(&alice), p.reserved_balance(&alice)), (100, 0));
+		let key = Pallet::<TestConfig>::account_key(PREFIX_RESERVED, &alice);
+		assert!(kv_store().get(&key).is_none());
+	}
+
+	#[test]
+	fn reserved_funds_keep_an_account_alive() {
+		let mut p = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		p.set_balance(&alice, 100);
+		p.reserve(&alice, 20).unwrap();
+		let keep_alive = ExistenceRequirement::KeepAlive;
+		assert_eq!(p.do_transfer(&alice, &bob, 80, keep_alive), Ok(()));
+		assert_eq!((p.balance(&alice), p.reserved_balance(&alice)), (0, 20));
+		assert!(p.take_reaped().is_empty());
+	}
+
+	#[test]
+	fn slash_reserved_burns_and_may_reap() {
+		let mut p = new();
+		let alice = "alice".to_string();
+		p.set_balance(&alice, 100);
+		let before = p.total_issuance();
+		p.reserve(&alice, 95).unwrap();
+		assert_eq!(p.slash_reserved(&alice, 50), 0);
+		assert_eq!(p.total_issuance(), before - 50);
+		assert!(p.take_reaped().is_empty());
+		assert_eq!(p.slash_reserved(&alice, 50), 5, "only 45 was left reserved");
+		assert_eq!((p.balance(&alice), p.reserved_balance(&alice)), (0, 0));
+		assert_eq!(p.take_reaped(), vec![alice], "the 5 free left was dust");
+		assert_eq!(p.total_issuance(), before - 100);
+	}
+
+	#[test]
+	fn repatriate_reserved_to_free_or_reserved() {
+		let mut p = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		p.set_balance(&alice, 100);
+		p.reserve(&alice, 50).unwrap();
+		assert!(p.repatriate_reserved(&alice, &bob, 5, BalanceStatus::Free).is_err());
+		assert_eq!(p.repatriate_reserved(&alice, &bob, 20, BalanceStatus::Free), Ok(0));
+		assert_eq!(p.repatriate_reserved(&alice, &bob, 40, BalanceStatus::Reserved), Ok(10));
+		assert_eq!((p.balance(&alice), p.reserved_balance(&alice)), (50, 0));
+		assert_eq!((p.balance(&bob), p.reserved_balance(&bob)), (20, 30));
+		assert_eq!(p.repatriate_reserved(&bob, &bob, 10, BalanceStatus::Free), Ok(0));
+		assert_eq!((p.balance(&bob), p.reserved_balance(&bob)), (30, 20));
+		assert_eq!(p.try_state(), Ok(()));
+	}
+
+	#[test]
+	fn locks_freeze_the_largest_amount() {
+		let mut p = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		p.set_balance(&alice, 100);
+		p.set_lock(*b"staking ", &alice, 60, None);
+		p.set_lock(*b"vesting ", &alice, 30, None);
+		assert_eq!(p.usable_balance(&alice), 40);
+		assert_eq!(
+			p.transfer(RuntimeOrigin::Signed(alice.clone()), bob.clone(), 41),
+			Err("Balance is locked.")
+		);
+		assert_eq!(p.reserve(&alice, 41), Err("Balance is locked."));
+		assert!(p.burn(&alice, 41, ExistenceRequirement::AllowDeath).is_err());
+		assert_eq!(p.transfer(RuntimeOrigin::Signed(alice.clone()), bob.clone(), 40), Ok(()));
+
+		p.set_lock(*b"staking ", &alice, 20, None);
+		assert_eq!(p.usable_balance(&alice), 30, "vesting lock now the largest");
+		p.remove_lock(*b"vesting ", &alice);
+		assert_eq!(p.usable_balance(&alice), 40);
+		assert_eq!(p.locks(&alice).len(), 1);
+	}
+
+	#[test]
+	fn locks_expire_at_their_block() {
+		let mut p = new();
+		let alice = "alice".to_string();
+		p.set_balance(&alice, 100);
+		p.on_initialize(5);
+		p.set_lock(*b"timelock", &alice, 100, Some(7));
+		p.set_lock(*b"short   ", &alice, 10, Some(6));
+		assert_eq!(p.usable_balance(&alice), 0);
+		p.on_initialize(7);
+		assert_eq!(p.usable_balance(&alice), 100);
+		assert_eq!(p.locks(&alice).len(), 2, "expired locks linger until rewritten");
+		p.remove_lock(*b"other   ", &alice);
+		assert!(p.locks(&alice).is_empty());
+	}
+
+	#[test]
+	fn reserves_and_locks_survive_reload() {
+		let mut p = new();
+		let carol = "carol".to_string();
+		p.set_balance(&carol, 100);
+		p.reserve(&carol, 25).unwrap();
+		p.set_lock(*b"staking ", &carol, 50, Some(9));
+		let reloaded = new();
+		assert_eq!(reloaded.reserved_balance(&carol), 25);
+		assert_eq!(reloaded.locks(&carol), p.locks(&carol));
+		assert_eq!(reloaded.frozen_balance(&carol), 50);
+	}
+
+	#[test]
+	fn transfer_all_leaves_locked_and_required_funds() {
+		let mut p = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		p.set_balance(&alice, 100);
+		p.set_lock(*b"staking ", &alice, 30, None);
+		assert_eq!(p.reducible_balance(&alice, false), 70);
+		p.reserve(&alice, 5).unwrap();
+		assert_eq!(p.reducible_balance(&alice, true), 65, "locks exceed what keep-alive needs");
+		p.remove_lock(*b"staking ", &alice);
+		assert_eq!(p.reducible_balance(&alice, true), 90, "reserve covers half the deposit");
+		assert_eq!(p.transfer_all(RuntimeOrigin::Signed(alice.clone()), bob.clone(), true), Ok(()));
+		assert_eq!((p.balance(&alice), p.balance(&bob)), (5, 90));
+	}
+
+	#[test]
+	fn force_calls_are_root_only() {
+		let mut p = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		p.set_balance(&alice, 100);
+		let signed = RuntimeOrigin::Signed(alice.clone());
+		let err = Err("bad origin: expected root");
+		assert_eq!(p.force_set_balance(signed.clone(), alice.clone(), 5), err);
+		assert_eq!(p.force_transfer(signed, alice.clone(), bob.clone(), 50), err);
+		assert_eq!(p.balance(&alice), 100);
+
+		p.force_transfer(RuntimeOrigin::Root, alice.clone(), bob.clone(), 50).unwrap();
+		p.force_set_balance(RuntimeOrigin::Root, bob.clone(), 80).unwrap();
+		assert_eq!((p.balance(&alice), p.balance(&bob)), (50, 80));
+	}
 }
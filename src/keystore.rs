@@ -0,0 +1,246 @@
+use crate::support::{AccountId32, CryptoScheme, MultiSigner, Pair};
+use chacha20poly1305::{
+	ChaCha20Poly1305, KeyInit,
+	aead::{Aead, OsRng, Payload, rand_core::RngCore},
+};
+use parity_scale_codec::{Decode, Encode};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// Extension of keystore entry files.
+const KEY_FILE_EXT: &str = "key";
+
+/// Public part of a keystore entry, readable without the password.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyInfo {
+	pub name: String,
+	pub public: MultiSigner,
+}
+
+impl KeyInfo {
+	pub fn account(&self) -> AccountId32 {
+		self.public.into_account()
+	}
+}
+
+/// On-disk entry, SCALE-encoded. The seed is sealed with ChaCha20-Poly1305 under a key derived
+/// from the password with Argon2id; the public key is bound in as associated data, so an entry
+/// whose public key was swapped fails to unlock.
+#[derive(Encode, Decode)]
+struct EncryptedKey {
+	name: String,
+	public: MultiSigner,
+	salt: [u8; 16],
+	nonce: [u8; 12],
+	ciphertext: Vec<u8>,
+}
+
+/// Directory of password-encrypted keys, one `<name>.key` file per account.
+pub struct Keystore {
+	dir: PathBuf,
+}
+
+impl Keystore {
+	/// Open the keystore at `dir`. The directory is created on the first write.
+	pub fn open(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	/// Generate a fresh random key under `name`.
+	pub fn generate(
+		&self,
+		name: &str,
+		scheme: CryptoScheme,
+		password: &str,
+	) -> Result<KeyInfo, String> {
+		loop {
+			let mut seed = Zeroizing::new([0u8; 32]);
+			OsRng.fill_bytes(&mut *seed);
+			// Only ecdsa can reject a seed (out-of-range scalar); just draw again.
+			if Pair::try_from_seed(scheme, &seed).is_ok() {
+				return self.import(name, scheme, &seed, password);
+			}
+		}
+	}
+
+	/// Store an existing 32-byte seed under `name`.
+	pub fn import(
+		&self,
+		name: &str,
+		scheme: CryptoScheme,
+		seed: &[u8; 32],
+		password: &str,
+	) -> Result<KeyInfo, String> {
+		validate_name(name)?;
+		let public = Pair::try_from_seed(scheme, seed)?.public();
+		let path = self.path_of(name);
+		if path.exists() {
+			return Err(format!("key '{name}' already exists"));
+		}
+		if let Some(existing) = self.find_account(&public.into_account())? {
+			return Err(format!("account is already stored as '{}'", existing.name));
+		}
+
+		let mut salt = [0u8; 16];
+		let mut nonce = [0u8; 12];
+		OsRng.fill_bytes(&mut salt);
+		OsRng.fill_bytes(&mut nonce);
+		let cipher = cipher(password, &salt)?;
+		let ciphertext = cipher
+			.encrypt(&nonce.into(), Payload { msg: seed, aad: &public.encode() })
+			.map_err(|_| "failed to encrypt key")?;
+
+		let entry = EncryptedKey { name: name.to_string(), public, salt, nonce, ciphertext };
+		std::fs::create_dir_all(&self.dir)
+			.map_err(|e| format!("failed to create keystore '{}': {e}", self.dir.display()))?;
+		write_private(&path, &entry.encode())
+			.map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+		Ok(KeyInfo { name: name.to_string(), public })
+	}
+
+	/// All stored keys, sorted by name. A missing directory is an empty keystore.
+	pub fn list(&self) -> Result<Vec<KeyInfo>, String> {
+		let entries = match std::fs::read_dir(&self.dir) {
+			Ok(entries) => entries,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(format!("failed to read keystore '{}': {e}", self.dir.display())),
+		};
+		let mut keys = Vec::new();
+		for entry in entries {
+			let path = entry.map_err(|e| format!("failed to read keystore: {e}"))?.path();
+			if path.extension().is_some_and(|ext| ext == KEY_FILE_EXT) {
+				let key = read_entry(&path)?;
+				keys.push(KeyInfo { name: key.name, public: key.public });
+			}
+		}
+		keys.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(keys)
+	}
+
+	/// Look up a key by name or by address (SS58 or hex).
+	pub fn find(&self, name_or_address: &str) -> Result<Option<KeyInfo>, String> {
+		let path = self.path_of(name_or_address);
+		if validate_name(name_or_address).is_ok() && path.exists() {
+			let key = read_entry(&path)?;
+			return Ok(Some(KeyInfo { name: key.name, public: key.public }));
+		}
+		match name_or_address.parse::<AccountId32>() {
+			Ok(account) => self.find_account(&account),
+			Err(_) => Ok(None),
+		}
+	}
+
+	/// Decrypt the key stored under `name_or_address`.
+	pub fn unlock(&self, name_or_address: &str, password: &str) -> Result<Pair, String> {
+		let info = self
+			.find(name_or_address)?
+			.ok_or_else(|| format!("no key '{name_or_address}' in the keystore"))?;
+		let key = read_entry(&self.path_of(&info.name))?;
+		let seed = cipher(password, &key.salt)?
+			.decrypt(&key.nonce.into(), Payload { msg: &key.ciphertext, aad: &key.public.encode() })
+			.map(Zeroizing::new)
+			.map_err(|_| "wrong password or corrupted key file")?;
+		let seed: &[u8; 32] =
+			seed.as_slice().try_into().map_err(|_| "corrupted key file: bad seed length")?;
+		let pair = Pair::try_from_seed(key.public.scheme(), seed)?;
+		if pair.public() != key.public {
+			return Err("corrupted key file: public key does not match seed".into());
+		}
+		Ok(pair)
+	}
+
+	fn find_account(&self, account: &AccountId32) -> Result<Option<KeyInfo>, String> {
+		Ok(self.list()?.into_iter().find(|key| key.account() == *account))
+	}
+
+	fn path_of(&self, name: &str) -> PathBuf {
+		self.dir.join(format!("{name}.{KEY_FILE_EXT}"))
+	}
+}
+
+/// Key names double as file names, so keep them to a safe alphabet.
+fn validate_name(name: &str) -> Result<(), String> {
+	let valid = !name.is_empty()
+		&& name.len() <= 64
+		&& name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+	if !valid {
+		return Err(format!("invalid key name '{name}'; use up to 64 of [A-Za-z0-9_-]"));
+	}
+	Ok(())
+}
+
+fn cipher(password: &str, salt: &[u8; 16]) -> Result<ChaCha20Poly1305, String> {
+	let mut key = Zeroizing::new([0u8; 32]);
+	argon2::Argon2::default()
+		.hash_password_into(password.as_bytes(), salt, &mut *key)
+		.map_err(|e| format!("key derivation failed: {e}"))?;
+	Ok(ChaCha20Poly1305::new(&(*key).into()))
+}
+
+fn read_entry(path: &Path) -> Result<EncryptedKey, String> {
+	let bytes =
+		std::fs::read(path).map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+	EncryptedKey::decode(&mut &bytes[..])
+		.map_err(|e| format!("corrupted key file '{}': {e}", path.display()))
+}
+
+/// Write a file readable only by the owner.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+	use std::io::Write;
+
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+	options.open(path)?.write_all(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn generate_then_unlock_by_name_and_address() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = Keystore::open(dir.path().join("keys"));
+		let info = keystore.generate("dave", CryptoScheme::Sr25519, "hunter2").unwrap();
+		assert_eq!(info.public.scheme(), CryptoScheme::Sr25519);
+
+		let by_name = keystore.unlock("dave", "hunter2").unwrap();
+		assert_eq!(by_name.public(), info.public);
+		let by_address = keystore.unlock(&info.account().to_string(), "hunter2").unwrap();
+		assert_eq!(by_address.public(), info.public);
+	}
+
+	#[test]
+	fn unlock_rejects_wrong_password() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = Keystore::open(dir.path());
+		keystore.import("eve", CryptoScheme::Ed25519, &[7u8; 32], "right").unwrap();
+		assert!(keystore.unlock("eve", "wrong").is_err());
+	}
+
+	#[test]
+	fn import_rejects_duplicates_and_bad_names() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = Keystore::open(dir.path());
+		keystore.import("eve", CryptoScheme::Ecdsa, &[7u8; 32], "pw").unwrap();
+		assert!(keystore.import("eve", CryptoScheme::Ed25519, &[8u8; 32], "pw").is_err());
+		assert!(keystore.import("eve2", CryptoScheme::Ecdsa, &[7u8; 32], "pw").is_err());
+		assert!(keystore.import("../eve", CryptoScheme::Ed25519, &[9u8; 32], "pw").is_err());
+		assert!(keystore.import("zero", CryptoScheme::Ecdsa, &[0u8; 32], "pw").is_err());
+	}
+
+	#[test]
+	fn list_is_sorted_and_needs_no_password() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = Keystore::open(dir.path());
+		assert!(keystore.list().unwrap().is_empty());
+		keystore.import("zed", CryptoScheme::Ed25519, &[1u8; 32], "pw").unwrap();
+		keystore.import("amy", CryptoScheme::Ecdsa, &[2u8; 32], "pw").unwrap();
+
+		let names: Vec<_> = keystore.list().unwrap().into_iter().map(|k| k.name).collect();
+		assert_eq!(names, ["amy", "zed"]);
+		assert!(keystore.find("nobody").unwrap().is_none());
+	}
+}
@@ -1,9 +1,20 @@
 use support::Dispatch;
 
+pub mod assets;
 pub mod balances;
+/// Password-encrypted on-disk keystore for accounts beyond the dev keyring.
+pub mod keystore;
+/// Binary Merkle trees over `blake2_256`, for state roots and the proofs checked against them.
+pub mod merkle;
+pub mod nfts;
 pub mod proof_of_existence;
+/// `support::Signer` implementations for keys kept outside the process: key files and a signer
+/// daemon on a Unix socket.
+pub mod signer;
+pub mod sudo;
 pub mod support;
 pub mod system;
+pub mod vesting;
 
 pub mod types {
 	pub type AccountId = crate::support::AccountId32;
@@ -13,16 +24,27 @@ pub mod types {
 	pub type Extrinsic = crate::support::UncheckedExtrinsic<crate::RuntimeCall>;
 	pub type Header = crate::support::Header<BlockNumber>;
 	pub type Block = crate::support::Block<Header, Extrinsic>;
-	pub type Content = String;
+	/// `blake2_256` digest of the claimed document; the document itself never goes on chain.
+	pub type Content = [u8; 32];
+	pub type AssetId = u32;
+	pub type CollectionId = u32;
+	pub type ItemId = u32;
 	pub type Mempool = crate::support::Mempool<Extrinsic>;
 }
 
 #[macros::runtime]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Runtime {
 	pub system: system::Pallet<Self>,
 	pub balances: balances::Pallet<Self>,
+	#[context(balances)]
 	pub proof_of_existence: proof_of_existence::Pallet<Self>,
+	#[context(balances)]
+	pub vesting: vesting::Pallet<Self>,
+	pub assets: assets::Pallet<Self>,
+	pub nfts: nfts::Pallet<Self>,
+	#[dispatches_as_root]
+	pub sudo: sudo::Pallet<Self>,
 }
 
 impl system::Config for Runtime {
@@ -33,10 +55,67 @@ impl system::Config for Runtime {
 
 impl balances::Config for Runtime {
 	type Balance = types::Balance;
+	const EXISTENTIAL_DEPOSIT: types::Balance = 10;
 }
 
 impl proof_of_existence::Config for Runtime {
 	type Content = types::Content;
+	type Metadata = String;
+	type Balance = types::Balance;
+	type Currency = balances::Pallet<Self>;
+	const CLAIM_DEPOSIT: types::Balance = 100;
+	const MAX_METADATA_LEN: usize = 256;
+	const MAX_CLAIMS_PER_ACCOUNT: u32 = 1_000;
+	const MAX_EXPIRIES_PER_BLOCK: u32 = 100;
+}
+
+impl vesting::Config for Runtime {
+	type Balance = types::Balance;
+	type Currency = balances::Pallet<Self>;
+	const MIN_VESTED_TRANSFER: types::Balance = 100;
+	const MAX_VESTING_SCHEDULES: usize = 28;
+}
+
+impl assets::Config for Runtime {
+	type AssetId = types::AssetId;
+	type Balance = types::Balance;
+	const STRING_LIMIT: usize = 50;
+}
+
+impl nfts::Config for Runtime {
+	type CollectionId = types::CollectionId;
+	type ItemId = types::ItemId;
+	/// Key/value pairs, e.g. `("title", "Deed #1")`.
+	type Attributes = Vec<(String, String)>;
+	const MAX_ATTRIBUTES_LEN: usize = 1024;
+}
+
+impl sudo::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	const OUTCOME_HISTORY: types::BlockNumber = 4_320;
+}
+
+impl support::RuntimeHooks for Runtime {
+	fn on_initialize(&mut self) {
+		let now = self.system.block_number();
+		self.balances.on_initialize(now);
+		self.vesting.on_initialize(now);
+		self.proof_of_existence.on_initialize(now, self.system.timestamp());
+		self.proof_of_existence.remove_expired(&mut self.balances);
+		self.sudo.on_initialize(now);
+	}
+
+	fn after_dispatch(&mut self) {
+		for who in self.balances.take_reaped() {
+			self.system.kill_account(&who);
+		}
+	}
+
+	/// Only proof-of-existence claims are committed to, so third parties can check them with
+	/// `proof_of_existence::verify_proof`.
+	fn state_root(&self) -> [u8; 32] {
+		self.proof_of_existence.state_root()
+	}
 }
 
 /// Seed dev accounts on a brand-new chain (block_number == 0) and execute the genesis block.
@@ -44,12 +123,22 @@ pub fn maybe_apply_genesis(runtime: &mut Runtime) {
 	if runtime.system.block_number() != 0 {
 		return;
 	}
-	use support::keyring::AccountKeyring::{Alice, Bob, Charlie};
-	runtime.balances.set_balance(&Alice.public(), 1_000_000);
-	runtime.balances.set_balance(&Bob.public(), 1_000_000);
-	runtime.balances.set_balance(&Charlie.public(), 1_000_000);
-
-	let genesis = types::Block { header: support::Header { block_number: 1 }, extrinsics: vec![] };
-	runtime.execute_block(genesis).expect("genesis block must succeed");
-	println!("[genesis] Alice / Bob / Charlie each funded with 1_000_000");
+	use support::{CryptoScheme, keyring::AccountKeyring};
+	for account in AccountKeyring::ALL {
+		for scheme in [CryptoScheme::Ed25519, CryptoScheme::Sr25519, CryptoScheme::Ecdsa] {
+			runtime
+				.balances
+				.mint(&account.public_for(scheme), 1_000_000)
+				.expect("genesis endowment must succeed");
+		}
+	}
+	// As in Substrate dev chains, Alice (ed25519) holds the sudo key.
+	runtime.sudo.initialize_key(AccountKeyring::Alice.public());
+
+	// A fixed timestamp keeps the genesis block identical on every node.
+	let header = support::Header { block_number: 1, timestamp: 0, state_root: [0; 32] };
+	let genesis = types::Block { header, extrinsics: vec![] };
+	runtime.author_block(genesis).expect("genesis block must succeed");
+	println!("[genesis] Alice / Bob / Charlie each funded with 1_000_000 per signature scheme");
+	println!("[genesis] Alice (ed25519) holds the sudo key");
 }
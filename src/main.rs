@@ -1,13 +1,18 @@
 use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 mod network;
 mod node;
 
 // Re-import from the library so child modules (node.rs) can reach them via `crate::*`.
 use rust_state_machine::{
-	Runtime, RuntimeCall, balances, maybe_apply_genesis, proof_of_existence, support, types,
+	Runtime, RuntimeCall, balances, keystore, maybe_apply_genesis, proof_of_existence, signer,
+	sudo, support, types,
 };
 
+/// Environment variable read for the keystore password before falling back to a prompt.
+const PASSWORD_ENV: &str = "KEYSTORE_PASSWORD";
+
 #[derive(Parser)]
 #[command(name = "rust-state-machine", version, about = "State machine")]
 struct Cli {
@@ -32,6 +37,14 @@ enum Commands {
 		/// Path to the RocksDB database directory (default: ./state.db).
 		#[arg(long)]
 		db_path: Option<String>,
+		/// File holding the libp2p node key (hex ed25519 secret); created if missing. Keeps the
+		/// peer id, and so the authorship order, stable across restarts.
+		#[arg(long, conflicts_with = "node_key")]
+		node_key_file: Option<PathBuf>,
+		/// libp2p node key as a hex ed25519 secret. Without this or `--node-key-file` the node
+		/// gets a random peer id on every start.
+		#[arg(long)]
+		node_key: Option<String>,
 	},
 	/// Print the current chain state (balances, nonces, PoE claims) and exit.
 	State {
@@ -45,8 +58,34 @@ enum Commands {
 		#[arg(long)]
 		db_path: Option<String>,
 	},
+	/// Manage the password-encrypted keystore.
+	/// The password is read from $KEYSTORE_PASSWORD, or prompted for.
+	Key {
+		#[command(subcommand)]
+		command: KeyCommand,
+		/// Keystore directory.
+		#[arg(long, global = true, default_value = "keystore")]
+		keystore: PathBuf,
+	},
+	/// Run a signer daemon holding one key, answering sign requests on a Unix socket.
+	SignerDaemon {
+		/// Key to serve: a dev-keyring account name or secret URI, a keystore key or `file:<path>`.
+		key: String,
+		/// Path of the Unix socket to listen on.
+		#[arg(long)]
+		socket: String,
+		/// Signature scheme when `key` is a dev account: ed25519, sr25519 or ecdsa.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+		/// Keystore directory.
+		#[arg(long, default_value = "keystore")]
+		keystore: PathBuf,
+	},
 	/// Submit a signed balance transfer into the next block.
-	/// The sender must be one of the dev-keyring accounts: alice, bob, charlie.
+	/// The sender is a dev-keyring account (alice, bob, charlie) or secret URI (`//Dave`,
+	/// `<mnemonic>//stash//0`), a keystore key by name or address, `file:<path>` for a key file,
+	/// or `unix:<path>` for a signer daemon socket.
+	/// The recipient is any of the above bar files and sockets, or an SS58 / hex address.
 	SubmitTransfer {
 		from: String,
 		to: String,
@@ -56,15 +95,160 @@ enum Commands {
 		/// If omitted, the transfer is executed locally in a one-shot runtime.
 		#[arg(long)]
 		node: Option<String>,
+		/// Signature scheme of the dev accounts: ed25519, sr25519 or ecdsa.
+		/// Applies to dev-account senders and the recipient, as each scheme derives a different
+		/// account.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+		/// Keystore directory.
+		#[arg(long, default_value = "keystore")]
+		keystore: PathBuf,
 	},
 	/// Submit a signed proof-of-existence claim into the next block.
-	/// The account must be one of the dev-keyring accounts: alice, bob, charlie.
+	/// The account is a dev-keyring account (alice, bob, charlie) or secret URI, a keystore key
+	/// by name or address, `file:<path>` for a key file, or `unix:<path>` for a signer daemon
+	/// socket.
+	/// The claim is the blake2-256 digest of `claim` or of the file given with `--file`; only the
+	/// digest goes on chain.
 	SubmitClaim {
 		account: String,
-		claim: String,
+		#[arg(required_unless_present = "file", conflicts_with = "file")]
+		claim: Option<String>,
+		/// Claim a file, hashed locally, instead of text.
+		#[arg(long)]
+		file: Option<PathBuf>,
+		/// Free-form description stored with the claim.
+		#[arg(long, default_value = "")]
+		metadata: String,
+		/// Block at which the claim expires and is removed, unless renewed. Never, if omitted.
+		#[arg(long)]
+		expires_at: Option<types::BlockNumber>,
 		/// HTTP RPC URL of a running node (e.g. http://127.0.0.1:8000).
 		#[arg(long)]
 		node: Option<String>,
+		/// Signature scheme of the dev account: ed25519, sr25519 or ecdsa.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+		/// Keystore directory.
+		#[arg(long, default_value = "keystore")]
+		keystore: PathBuf,
+	},
+	/// Look up who holds a content digest (`0x<hex>`), when it was claimed and its past owners.
+	GetClaim {
+		digest: String,
+		/// HTTP RPC URL of a running node to ask. Without it the local database is read.
+		#[arg(long, conflicts_with = "db_path")]
+		node: Option<String>,
+		/// Path to the RocksDB database directory (default: ./state.db).
+		#[arg(long)]
+		db_path: Option<String>,
+	},
+	/// Check a claim proof from `GET /claim/:digest/proof` against a block header, without a node
+	/// or database. Exits with status 1 if the proof does not hold.
+	VerifyProof {
+		/// The block header, `0x`-prefixed SCALE, from a source you trust.
+		#[arg(long)]
+		header: String,
+		/// The claim proof, `0x`-prefixed SCALE.
+		#[arg(long)]
+		proof: String,
+	},
+	/// List the digests of the claims an account owns, a page at a time.
+	/// The account is an SS58 / hex address, a dev account name or secret URI, or a keystore key.
+	Claims {
+		#[arg(long)]
+		account: String,
+		/// Number of claims to skip.
+		#[arg(long, default_value_t = 0)]
+		start: usize,
+		/// Most claims to list; a node returns at most 100.
+		#[arg(long, default_value_t = 100)]
+		limit: usize,
+		/// HTTP RPC URL of a running node to ask. Without it the local database is read.
+		#[arg(long, conflicts_with = "db_path")]
+		node: Option<String>,
+		/// Path to the RocksDB database directory (default: ./state.db).
+		#[arg(long)]
+		db_path: Option<String>,
+		/// Signature scheme when `account` is a dev account: ed25519, sr25519 or ecdsa.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+		/// Keystore directory.
+		#[arg(long, default_value = "keystore")]
+		keystore: PathBuf,
+	},
+	/// Submit a privileged call, dispatched as root by the sudo pallet, into the next block.
+	/// Accounts are given as for `submit-transfer`.
+	Sudo {
+		#[command(subcommand)]
+		command: SudoCommand,
+		/// Account holding the sudo key; Alice (ed25519) from genesis.
+		#[arg(long, global = true, default_value = "alice")]
+		key: String,
+		/// HTTP RPC URL of a running node (e.g. http://127.0.0.1:8000).
+		/// If omitted, the call is executed locally in a one-shot runtime.
+		#[arg(long, global = true)]
+		node: Option<String>,
+		/// Signature scheme of the dev accounts: ed25519, sr25519 or ecdsa.
+		#[arg(long, global = true, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+		/// Keystore directory.
+		#[arg(long, global = true, default_value = "keystore")]
+		keystore: PathBuf,
+	},
+	/// Hash a file and check whether, and by whom, it was claimed. Exits with status 1 if not.
+	VerifyClaim {
+		#[arg(long)]
+		file: PathBuf,
+		/// HTTP RPC URL of a running node to ask. Without it the local database is read.
+		#[arg(long, conflicts_with = "db_path")]
+		node: Option<String>,
+		/// Path to the RocksDB database directory (default: ./state.db).
+		#[arg(long)]
+		db_path: Option<String>,
+	},
+}
+
+#[derive(Subcommand)]
+enum SudoCommand {
+	/// Set an account's free balance, minting or burning the difference.
+	SetBalance { who: String, free: types::Balance },
+	/// Move funds out of any account.
+	Transfer { source: String, to: String, amount: types::Balance },
+	/// Delete a claim by its content digest (`0x<hex>`), returning the deposit to its owner.
+	RevokeClaim { digest: String },
+	/// Hand the sudo key to another account.
+	SetKey { new: String },
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+	/// Generate a new random key and store it under `name`.
+	Generate {
+		name: String,
+		/// Signature scheme: ed25519, sr25519 or ecdsa.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+	},
+	/// List stored keys with their addresses. Needs no password.
+	List,
+	/// Store an existing 32-byte seed (`0x<hex>`) under `name`.
+	Import {
+		name: String,
+		seed: String,
+		/// Signature scheme: ed25519, sr25519 or ecdsa.
+		#[arg(long, default_value = "ed25519")]
+		scheme: support::CryptoScheme,
+	},
+	/// Print the public key and address of a stored key. Needs no password.
+	ExportPublic {
+		/// Key name or address.
+		key: String,
+	},
+	/// Unlock a stored key and print its details, checking the password.
+	Inspect {
+		/// Key name or address.
+		key: String,
 	},
 }
 
@@ -72,17 +256,26 @@ fn main() {
 	let cli = Cli::parse();
 
 	match cli.command {
-		Commands::Start { port, peer, rpc_port, db_path } => {
+		Commands::Start { port, peer, rpc_port, db_path, node_key_file, node_key } => {
 			if let Some(path) = db_path {
 				support::init_db_path(&path);
 			}
 			let dial_addr =
 				peer.map(|s| s.parse::<libp2p::Multiaddr>().expect("invalid multiaddr"));
+			let node_key = match (node_key_file, node_key) {
+				(Some(path), _) => network::load_or_create_node_key(&path),
+				(None, Some(hex)) => network::node_key_from_hex(&hex),
+				(None, None) => {
+					println!("[net] using a random node key; pass --node-key-file to keep it");
+					Ok(libp2p::identity::Keypair::generate_ed25519())
+				},
+			}
+			.unwrap_or_else(|e| panic!("{e}"));
 			tokio::runtime::Builder::new_multi_thread()
 				.enable_all()
 				.build()
 				.unwrap()
-				.block_on(node::run(port, dial_addr, rpc_port))
+				.block_on(node::run(port, dial_addr, rpc_port, node_key))
 				.unwrap();
 		},
 		Commands::State { db_path } => {
@@ -102,9 +295,38 @@ fn main() {
 				println!("Nothing to reset — '{path}' does not exist");
 			}
 		},
-		Commands::SubmitTransfer { from, to, amount, node } =>
-			submit_transfer(from, to, amount, node),
-		Commands::SubmitClaim { account, claim, node } => submit_claim(account, claim, node),
+		Commands::Key { command, keystore } => run_key_command(command, &keystore),
+		Commands::SignerDaemon { key, socket, scheme, keystore } => {
+			if key.starts_with("unix:") {
+				panic!("a signer daemon cannot forward to another daemon");
+			}
+			let signer = resolve_signer(&key, scheme, &keystore);
+			signer::serve(&socket, &*signer).unwrap_or_else(|e| panic!("signer daemon: {e}"));
+		},
+		Commands::SubmitTransfer { from, to, amount, node, scheme, keystore } =>
+			submit_transfer(from, to, amount, node, scheme, &keystore),
+		Commands::SubmitClaim {
+			account,
+			claim,
+			file,
+			metadata,
+			expires_at,
+			node,
+			scheme,
+			keystore,
+		} => {
+			let claim = claim_digest(claim, file);
+			submit_claim(account, claim, metadata, expires_at, node, scheme, &keystore)
+		},
+		Commands::GetClaim { digest, node, db_path } => get_claim(digest, node, db_path),
+		Commands::Sudo { command, key, node, scheme, keystore } =>
+			submit_sudo(command, key, node, scheme, &keystore),
+		Commands::VerifyClaim { file, node, db_path } => verify_claim(file, node, db_path),
+		Commands::VerifyProof { header, proof } => verify_proof(&header, &proof),
+		Commands::Claims { account, start, limit, node, db_path, scheme, keystore } => {
+			let account = resolve_account(&account, scheme, &keystore);
+			list_claims(&account, start, limit, node, db_path)
+		},
 	}
 }
 
@@ -122,292 +344,623 @@ fn run_demo() {
 	let bob_sk = Bob.signing_key();
 	let charlie_sk = Charlie.signing_key();
 
-	runtime.balances.set_balance(&alice, 100);
+	runtime.balances.mint(&alice, 100).unwrap();
 
 	let (mut an, mut bn, mut cn) = (0u32, 0u32, 0u32); // alice, bob, charlie nonces
+	let until = support::now_millis() + support::EXTRINSIC_LIFETIME_MILLIS;
 
 	// --- Block 1 ---
 	let block_1 = types::Block {
-		header: support::Header { block_number: 1 },
+		header: support::Header { block_number: 1, timestamp: 0, state_root: [0; 32] },
 		extrinsics: vec![
 			{
 				let call = RuntimeCall::balances(balances::Call::transfer { to: bob, amount: 70 });
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 20 });
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 30 });
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 		],
 	};
-	let _res1 = runtime.execute_block(block_1).map_err(|e| eprintln!("{e}"));
+	let _res1 = runtime.author_block(block_1).map_err(|e| eprintln!("{e}"));
 
 	// --- Block 2 ---
 	let block_2 = types::Block {
-		header: support::Header { block_number: 2 },
+		header: support::Header { block_number: 2, timestamp: 0, state_root: [0; 32] },
 		extrinsics: vec![{
 			let call = RuntimeCall::balances(balances::Call::transfer { to: alice, amount: 40 });
-			let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, call);
+			let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, until, call);
 			cn += 1;
 			ext
 		}],
 	};
-	let _res2 = runtime.execute_block(block_2).map_err(|e| eprintln!("{e}"));
+	let _res2 = runtime.author_block(block_2).map_err(|e| eprintln!("{e}"));
 
 	// --- Block 3 ---
 	let block_3 = types::Block {
-		header: support::Header { block_number: 3 },
+		header: support::Header { block_number: 3, timestamp: 0, state_root: [0; 32] },
 		extrinsics: vec![
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-						claim: "My first document".to_string(),
+						claim: support::blake2_256(b"My first document"),
+						metadata: String::new(),
+						expires_at: None,
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 			{
 				let call = RuntimeCall::balances(balances::Call::transfer { to: alice, amount: 5 });
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-						claim: "Patent for my invention".to_string(),
+						claim: support::blake2_256(b"Patent for my invention"),
+						metadata: String::new(),
+						expires_at: None,
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-						claim: "Copyright on my work".to_string(),
+						claim: support::blake2_256(b"Copyright on my work"),
+						metadata: String::new(),
+						expires_at: None,
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, until, call);
 				cn += 1;
 				ext
 			},
 		],
 	};
-	let _res3 = runtime.execute_block(block_3).map_err(|e| eprintln!("{e}"));
+	let _res3 = runtime.author_block(block_3).map_err(|e| eprintln!("{e}"));
 
 	// --- Block 4 ---
 	let block_4 = types::Block {
-		header: support::Header { block_number: 4 },
+		header: support::Header { block_number: 4, timestamp: 0, state_root: [0; 32] },
 		extrinsics: vec![
 			{
 				let call = RuntimeCall::balances(balances::Call::transfer { to: bob, amount: 10 });
-				let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, until, call);
 				cn += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-						claim: "My first document".to_string(),
+						claim: support::blake2_256(b"My first document"),
+						metadata: String::new(),
+						expires_at: None,
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-						claim: "My first document".to_string(),
+						claim: support::blake2_256(b"My first document"),
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 		],
 	};
-	let _res4 = runtime.execute_block(block_4).map_err(|e| eprintln!("{e}"));
+	let _res4 = runtime.author_block(block_4).map_err(|e| eprintln!("{e}"));
 
 	// --- Block 5 ---
 	let block_5 = types::Block {
-		header: support::Header { block_number: 5 },
+		header: support::Header { block_number: 5, timestamp: 0, state_root: [0; 32] },
 		extrinsics: vec![
 			{
 				let call =
 					RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 3 });
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-						claim: "Non-existent claim".to_string(),
+						claim: support::blake2_256(b"Non-existent claim"),
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call);
 				an += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-						claim: "Patent for my invention".to_string(),
+						claim: support::blake2_256(b"Patent for my invention"),
 					});
-				support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, call)
+				support::UncheckedExtrinsic::new_signed(&charlie_sk, cn, until, call)
 			},
 			{
 				let call =
 					RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-						claim: "Patent for my invention".to_string(),
+						claim: support::blake2_256(b"Patent for my invention"),
 					});
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 			{
 				let call =
 					RuntimeCall::balances(balances::Call::transfer { to: alice, amount: 15 });
-				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call);
+				let ext = support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call);
 				bn += 1;
 				ext
 			},
 		],
 	};
-	let _res5 = runtime.execute_block(block_5).map_err(|e| eprintln!("{e}"));
+	let _res5 = runtime.author_block(block_5).map_err(|e| eprintln!("{e}"));
 
 	// --- Mempool demo ---
 	let mut mempool = types::Mempool::new();
 	let _ = mempool.submit({
 		let call = RuntimeCall::balances(balances::Call::transfer { to: bob, amount: 1 });
-		support::UncheckedExtrinsic::new_signed(&alice_sk, an, call)
+		support::UncheckedExtrinsic::new_signed(&alice_sk, an, until, call)
 	});
 	let _ = mempool.submit({
 		let call = RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 2 });
-		support::UncheckedExtrinsic::new_signed(&bob_sk, bn, call)
+		support::UncheckedExtrinsic::new_signed(&bob_sk, bn, until, call)
 	});
 
 	let batch = mempool.drain_for_block(2);
 	let block_from_mempool = types::Block {
 		header: support::Header {
 			block_number: runtime.system.block_number().checked_add(1u32).unwrap(),
+			timestamp: 0,
+			state_root: [0; 32],
 		},
 		extrinsics: batch,
 	};
 	let _res_mempool = runtime
-		.execute_block(block_from_mempool)
+		.author_block(block_from_mempool)
 		.map_err(|e| eprintln!("Mempool block: {e}"));
 
 	println!("{runtime:#?}");
 }
 
-fn submit_transfer(from: String, to: String, amount: types::Balance, node: Option<String>) {
+fn run_key_command(command: KeyCommand, keystore: &Path) {
+	let keystore = keystore::Keystore::open(keystore);
+	match command {
+		KeyCommand::Generate { name, scheme } => {
+			let password = read_password(true);
+			let key = keystore.generate(&name, scheme, &password).unwrap_or_else(|e| panic!("{e}"));
+			println!("Generated {scheme} key '{name}': {}", key.account());
+		},
+		KeyCommand::List => {
+			for key in keystore.list().unwrap_or_else(|e| panic!("{e}")) {
+				println!("{:<16} {:<8} {}", key.name, key.public.scheme(), key.account());
+			}
+		},
+		KeyCommand::Import { name, seed, scheme } => {
+			let seed: [u8; 32] = hex::decode(seed.trim_start_matches("0x"))
+				.ok()
+				.and_then(|bytes| bytes.try_into().ok())
+				.unwrap_or_else(|| panic!("seed must be 32 bytes of hex"));
+			let password = read_password(true);
+			let key = keystore
+				.import(&name, scheme, &seed, &password)
+				.unwrap_or_else(|e| panic!("{e}"));
+			println!("Imported {scheme} key '{name}': {}", key.account());
+		},
+		KeyCommand::ExportPublic { key } => {
+			let key = find_key(&keystore, &key);
+			println!("{}", key.account());
+			println!("{}:0x{}", key.public.scheme(), hex::encode(key.public.as_bytes()));
+		},
+		KeyCommand::Inspect { key } => {
+			let info = find_key(&keystore, &key);
+			let pair =
+				keystore.unlock(&key, &read_password(false)).unwrap_or_else(|e| panic!("{e}"));
+			let account = pair.public().into_account();
+			println!("Name:       {}", info.name);
+			println!("Scheme:     {}", pair.public().scheme());
+			println!("Public key: 0x{}", hex::encode(pair.public().as_bytes()));
+			println!("Account id: 0x{}", hex::encode(account.as_bytes()));
+			println!("Address:    {account}");
+		},
+	}
+}
+
+fn find_key(keystore: &keystore::Keystore, key: &str) -> keystore::KeyInfo {
+	keystore
+		.find(key)
+		.unwrap_or_else(|e| panic!("{e}"))
+		.unwrap_or_else(|| panic!("no key '{key}' in the keystore"))
+}
+
+/// Keystore password from `$KEYSTORE_PASSWORD`, else prompted for (twice when `confirm`).
+fn read_password(confirm: bool) -> String {
+	if let Ok(password) = std::env::var(PASSWORD_ENV) {
+		return password;
+	}
+	let password =
+		rpassword::prompt_password("Keystore password: ").expect("failed to read password");
+	if confirm {
+		let again =
+			rpassword::prompt_password("Repeat password: ").expect("failed to read password");
+		assert!(password == again, "passwords do not match");
+	}
+	password
+}
+
+/// Resolve the sender of a `submit-*` command: a keystore key by name or address (unlocked
+/// with the keystore password), a dev account name or secret URI such as `//Dave` (signed
+/// in-memory with `scheme`), `file:<path>` for a key file, or `unix:<path>` for a signer daemon
+/// socket. The keystore comes before secret URIs, so `0x<hex>` naming a stored key's account
+/// signs with that key rather than being taken as a raw seed.
+fn resolve_signer(
+	spec: &str,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+) -> Box<dyn support::Signer> {
+	resolve_signer_with(spec, scheme, keystore, || read_password(false))
+}
+
+/// `resolve_signer`, asking `password` for the keystore password if a keystore key is used.
+fn resolve_signer_with(
+	spec: &str,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+	password: impl FnOnce() -> String,
+) -> Box<dyn support::Signer> {
+	if let Some(path) = spec.strip_prefix("file:") {
+		let signer = signer::FileSigner::load(path).unwrap_or_else(|e| panic!("{e}"));
+		return Box::new(signer);
+	}
+	if let Some(path) = spec.strip_prefix("unix:") {
+		return Box::new(signer::SocketSigner::new(path));
+	}
+	let keystore = keystore::Keystore::open(keystore);
+	if keystore.find(spec).unwrap_or_else(|e| panic!("{e}")).is_some() {
+		return Box::new(keystore.unlock(spec, &password()).unwrap_or_else(|e| panic!("{e}")));
+	}
+	match support::keyring::from_name(spec) {
+		Some(uri) => Box::new(uri.pair(scheme).unwrap_or_else(|e| panic!("{e}"))),
+		None => panic!(
+			"unknown account '{spec}'; use alice / bob / charlie, a secret URI, a keystore key, \
+			 file:<path> or unix:<path>"
+		),
+	}
+}
+
+/// Resolve an SS58 / hex address, a dev account name or secret URI (under `scheme`), or a
+/// keystore key name. Addresses come first, so `0x<hex>` is an account id rather than a seed.
+fn resolve_account(
+	spec: &str,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+) -> support::AccountId32 {
+	if let Ok(account) = spec.parse() {
+		return account;
+	}
+	if let Some(uri) = support::keyring::from_name(spec) {
+		return uri.pair(scheme).unwrap_or_else(|e| panic!("{e}")).public().into_account();
+	}
+	match keystore::Keystore::open(keystore).find(spec).unwrap_or_else(|e| panic!("{e}")) {
+		Some(key) => key.account(),
+		None => panic!(
+			"unknown account '{spec}'; use alice / bob / charlie, a secret URI, a keystore key or \
+			 an address"
+		),
+	}
+}
+
+/// Fetch the pending nonce of `account` from a running node.
+fn fetch_nonce(url: &str, account: &support::AccountId32) -> u32 {
+	ureq::get(&format!("{url}/nonce/{account}"))
+		.call()
+		.unwrap_or_else(|e| panic!("failed to get nonce: {e}"))
+		.into_string()
+		.unwrap()
+		.trim()
+		.parse()
+		.expect("nonce must be a number")
+}
+
+/// Sign `call` with `signer` at the node's pending nonce and submit it over RPC.
+fn submit_to_node(url: &str, signer: &dyn support::Signer, call: RuntimeCall) {
 	use parity_scale_codec::Encode;
 
-	let from_kr = support::keyring::from_name(&from)
-		.unwrap_or_else(|| panic!("unknown account '{from}'; use alice / bob / charlie"));
-	let to_kr = support::keyring::from_name(&to)
-		.unwrap_or_else(|| panic!("unknown account '{to}'; use alice / bob / charlie"));
+	let account = signer.public().unwrap_or_else(|e| panic!("{e}")).into_account();
+	let nonce = fetch_nonce(url, &account);
+	let valid_until = support::now_millis() + support::EXTRINSIC_LIFETIME_MILLIS;
+	let ext = support::UncheckedExtrinsic::sign_with(signer, nonce, valid_until, call)
+		.unwrap_or_else(|e| panic!("signing failed: {e}"));
+	match ureq::post(&format!("{url}/submit"))
+		.set("Content-Type", "application/octet-stream")
+		.send_bytes(&ext.encode())
+	{
+		Ok(res) => println!(
+			"Submitted (HTTP {}): {}",
+			res.status(),
+			res.into_string().unwrap_or_default().trim()
+		),
+		Err(ureq::Error::Status(code, res)) => {
+			eprintln!("Server error {code}: {}", res.into_string().unwrap_or_default())
+		},
+		Err(e) => eprintln!("Connection error: {e}"),
+	}
+}
+
+/// Execute `call` signed by `signer` in a one-shot local runtime and print the resulting state.
+fn execute_locally(mut runtime: Runtime, signer: &dyn support::Signer, call: RuntimeCall) {
+	let account = signer.public().unwrap_or_else(|e| panic!("{e}")).into_account();
+	let nonce = runtime.system.nonce(&account);
+	let valid_until = support::now_millis() + support::EXTRINSIC_LIFETIME_MILLIS;
+	let ext = support::UncheckedExtrinsic::sign_with(signer, nonce, valid_until, call)
+		.unwrap_or_else(|e| panic!("signing failed: {e}"));
+	let next_block_number = runtime.system.block_number().checked_add(1u32).unwrap();
+	let block = types::Block {
+		header: support::Header {
+			block_number: next_block_number,
+			timestamp: support::now_millis().max(runtime.system.timestamp()),
+			state_root: [0; 32],
+		},
+		extrinsics: vec![ext],
+	};
+	match runtime.author_block(block) {
+		Ok(_) => println!("{runtime:#?}"),
+		Err(e) => eprintln!("Execution error: {e}"),
+	}
+}
+
+fn submit_transfer(
+	from: String,
+	to: String,
+	amount: types::Balance,
+	node: Option<String>,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+) {
+	let signer = resolve_signer(&from, scheme, keystore);
+	let to = resolve_account(&to, scheme, keystore);
 
-	let call = RuntimeCall::balances(balances::Call::transfer { to: to_kr.public(), amount });
+	let call = RuntimeCall::balances(balances::Call::transfer { to, amount });
 
 	if let Some(url) = node {
-		let signer_pub = from_kr.public();
-		let account_hex: String =
-			signer_pub.as_bytes().iter().map(|b| format!("{b:02x}")).collect();
-		let nonce: u32 = ureq::get(&format!("{url}/nonce/{account_hex}"))
-			.call()
-			.unwrap_or_else(|e| panic!("failed to get nonce: {e}"))
-			.into_string()
-			.unwrap()
-			.trim()
-			.parse()
-			.expect("nonce must be a number");
-		let ext = support::UncheckedExtrinsic::new_signed(&from_kr.signing_key(), nonce, call);
-		let bytes = ext.encode();
-		match ureq::post(&format!("{url}/submit"))
-			.set("Content-Type", "application/octet-stream")
-			.send_bytes(&bytes)
-		{
-			Ok(res) => println!("Submitted (HTTP {})", res.status()),
-			Err(ureq::Error::Status(code, res)) => {
-				eprintln!("Server error {code}: {}", res.into_string().unwrap_or_default())
-			},
-			Err(e) => eprintln!("Connection error: {e}"),
-		}
+		submit_to_node(&url, &*signer, call);
 	} else {
 		let mut runtime = Runtime::new();
-		let signer_pub = from_kr.public();
-		runtime.balances.set_balance(&signer_pub, amount * 10);
-		let nonce = runtime.system.nonce(&signer_pub);
-		let ext = support::UncheckedExtrinsic::new_signed(&from_kr.signing_key(), nonce, call);
-		let next_block_number = runtime.system.block_number().checked_add(1u32).unwrap();
-		let block = types::Block {
-			header: support::Header { block_number: next_block_number },
-			extrinsics: vec![ext],
-		};
-		match runtime.execute_block(block) {
-			Ok(()) => println!("{runtime:#?}"),
-			Err(e) => eprintln!("Execution error: {e}"),
-		}
+		let signer_pub = signer.public().unwrap_or_else(|e| panic!("{e}")).into_account();
+		runtime
+			.balances
+			.mint(&signer_pub, amount * 10)
+			.unwrap_or_else(|e| panic!("{e}"));
+		execute_locally(runtime, &*signer, call);
 	}
 }
 
-fn submit_claim(account: String, claim: String, node: Option<String>) {
-	use parity_scale_codec::Encode;
+fn submit_claim(
+	account: String,
+	claim: types::Content,
+	metadata: String,
+	expires_at: Option<types::BlockNumber>,
+	node: Option<String>,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+) {
+	let signer = resolve_signer(&account, scheme, keystore);
+	println!("Claiming digest 0x{}", hex::encode(claim));
 
-	let kr = support::keyring::from_name(&account)
-		.unwrap_or_else(|| panic!("unknown account '{account}'; use alice / bob / charlie"));
+	let call = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+		claim,
+		metadata,
+		expires_at,
+	});
 
-	let call = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim });
+	if let Some(url) = node {
+		submit_to_node(&url, &*signer, call);
+	} else {
+		execute_locally(Runtime::new(), &*signer, call);
+	}
+}
+
+fn submit_sudo(
+	command: SudoCommand,
+	key: String,
+	node: Option<String>,
+	scheme: support::CryptoScheme,
+	keystore: &Path,
+) {
+	let signer = resolve_signer(&key, scheme, keystore);
+	let account = |name: &str| resolve_account(name, scheme, keystore);
+	let as_root = |call| RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(call) });
+	let call = match command {
+		SudoCommand::SetBalance { who, free } =>
+			as_root(RuntimeCall::balances(balances::Call::force_set_balance {
+				who: account(&who),
+				new_free: free,
+			})),
+		SudoCommand::Transfer { source, to, amount } =>
+			as_root(RuntimeCall::balances(balances::Call::force_transfer {
+				source: account(&source),
+				to: account(&to),
+				amount,
+			})),
+		SudoCommand::RevokeClaim { digest } => {
+			let claim = support::parse_hash(&digest).unwrap_or_else(|e| panic!("{e}"));
+			as_root(RuntimeCall::proof_of_existence(proof_of_existence::Call::force_revoke_claim {
+				claim,
+			}))
+		},
+		// Handing over the key is the key's own call, not one made as root.
+		SudoCommand::SetKey { new } =>
+			RuntimeCall::sudo(sudo::Call::set_key { new: account(&new) }),
+	};
 
 	if let Some(url) = node {
-		let signer_pub = kr.public();
-		let account_hex: String =
-			signer_pub.as_bytes().iter().map(|b| format!("{b:02x}")).collect();
-		let nonce: u32 = ureq::get(&format!("{url}/nonce/{account_hex}"))
-			.call()
-			.unwrap_or_else(|e| panic!("failed to get nonce: {e}"))
-			.into_string()
-			.unwrap()
-			.trim()
-			.parse()
-			.expect("nonce must be a number");
-		let ext = support::UncheckedExtrinsic::new_signed(&kr.signing_key(), nonce, call);
-		let bytes = ext.encode();
-		match ureq::post(&format!("{url}/submit"))
-			.set("Content-Type", "application/octet-stream")
-			.send_bytes(&bytes)
-		{
-			Ok(res) => println!("Submitted (HTTP {})", res.status()),
-			Err(ureq::Error::Status(code, res)) => {
-				eprintln!("Server error {code}: {}", res.into_string().unwrap_or_default())
-			},
-			Err(e) => eprintln!("Connection error: {e}"),
-		}
+		submit_to_node(&url, &*signer, call);
 	} else {
-		let mut runtime = Runtime::new();
-		let signer_pub = kr.public();
-		let nonce = runtime.system.nonce(&signer_pub);
-		let ext = support::UncheckedExtrinsic::new_signed(&kr.signing_key(), nonce, call);
-		let next_block_number = runtime.system.block_number().checked_add(1u32).unwrap();
-		let block = types::Block {
-			header: support::Header { block_number: next_block_number },
-			extrinsics: vec![ext],
+		execute_locally(Runtime::new(), &*signer, call);
+	}
+}
+
+/// The on-chain content of a claim: the `blake2_256` digest of `file`'s contents, or of `text`.
+fn claim_digest(text: Option<String>, file: Option<PathBuf>) -> types::Content {
+	match file {
+		Some(path) => file_digest(&path),
+		None => support::blake2_256(text.expect("clap requires a claim or --file").as_bytes()),
+	}
+}
+
+fn file_digest(path: &Path) -> types::Content {
+	std::fs::File::open(path)
+		.and_then(support::blake2_256_reader)
+		.unwrap_or_else(|e| panic!("failed to hash '{}': {e}", path.display()))
+}
+
+/// The claim on `digest`, rendered as by `GET /claim/:content`, from a running node or else the
+/// local database.
+fn lookup_claim(
+	digest: &types::Content,
+	node: Option<String>,
+	db_path: Option<String>,
+) -> Option<String> {
+	if let Some(url) = node {
+		return match ureq::get(&format!("{url}/claim/0x{}", hex::encode(digest))).call() {
+			Ok(res) => Some(res.into_string().unwrap_or_default()),
+			Err(ureq::Error::Status(404, _)) => None,
+			Err(e) => panic!("failed to get claim: {e}"),
 		};
-		match runtime.execute_block(block) {
-			Ok(()) => println!("{runtime:#?}"),
-			Err(e) => eprintln!("Execution error: {e}"),
+	}
+
+	if let Some(path) = db_path {
+		support::init_db_path(&path);
+	}
+	let poe = Runtime::new().proof_of_existence;
+	let info = poe.get_claim(digest)?;
+	let history: String = poe.history(digest).iter().map(|r| format!("\n  {r}")).collect();
+	Some(format!("{info}\nhistory:{history}"))
+}
+
+fn get_claim(digest: String, node: Option<String>, db_path: Option<String>) {
+	let digest = support::parse_hash(&digest).unwrap_or_else(|e| panic!("{e}"));
+	match lookup_claim(&digest, node, db_path) {
+		Some(info) => println!("{info}"),
+		None => println!("0x{} has not been claimed", hex::encode(digest)),
+	}
+}
+
+/// Print whether, and by whom, `file` was claimed. Exits with status 1 if it was not.
+fn verify_claim(file: PathBuf, node: Option<String>, db_path: Option<String>) {
+	let digest = file_digest(&file);
+	println!("{}: digest 0x{}", file.display(), hex::encode(digest));
+	match lookup_claim(&digest, node, db_path) {
+		Some(info) => println!("{info}"),
+		None => {
+			println!("not claimed");
+			std::process::exit(1);
+		},
+	}
+}
+
+/// Print `account`'s claims as `GET /claims/:account` does, from a running node or else the local
+/// database.
+fn list_claims(
+	account: &support::AccountId32,
+	start: usize,
+	limit: usize,
+	node: Option<String>,
+	db_path: Option<String>,
+) {
+	if let Some(url) = node {
+		let url = format!("{url}/claims/{account}?start={start}&limit={limit}");
+		let page = ureq::get(&url)
+			.call()
+			.unwrap_or_else(|e| panic!("failed to list claims: {e}"))
+			.into_string()
+			.unwrap_or_default();
+		println!("{page}");
+		return;
+	}
+
+	if let Some(path) = db_path {
+		support::init_db_path(&path);
+	}
+	let claims = Runtime::new().proof_of_existence.claims_of(account);
+	println!("total: {}", claims.len());
+	for claim in claims.iter().skip(start).take(limit) {
+		println!("0x{}", hex::encode(claim));
+	}
+}
+
+/// Decode `0x`-prefixed (or bare) hex SCALE as `T`, naming `what` in the error.
+fn decode_hex<T: parity_scale_codec::Decode>(what: &str, hex: &str) -> T {
+	let bytes = hex::decode(hex.trim_start_matches("0x"))
+		.unwrap_or_else(|e| panic!("invalid {what} hex: {e}"));
+	T::decode(&mut &bytes[..]).unwrap_or_else(|e| panic!("invalid {what}: {e}"))
+}
+
+/// Print the claim in `proof` if it holds against `header`'s state root. Exits with status 1 if
+/// it does not.
+fn verify_proof(header: &str, proof: &str) {
+	let header: types::Header = decode_hex("header", header);
+	let proof: proof_of_existence::ClaimProofOf<Runtime> = decode_hex("proof", proof);
+	if !proof_of_existence::verify_proof(&header, &proof) {
+		println!("proof does NOT match the state root of block #{}", header.block_number);
+		std::process::exit(1);
+	}
+	println!(
+		"0x{} is claimed as of block #{} (state root 0x{})\n{}",
+		hex::encode(proof.claim),
+		header.block_number,
+		hex::encode(header.state_root),
+		proof.info
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use support::CryptoScheme;
+
+	#[test]
+	fn signer_given_by_hex_address_is_the_keystore_key() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = keystore::Keystore::open(dir.path());
+		let info = keystore.import("dave", CryptoScheme::Ed25519, &[7u8; 32], "pw").unwrap();
+		let hex_address = format!("0x{}", hex::encode(info.account().as_bytes()));
+
+		for spec in [hex_address, info.account().to_string(), "dave".to_string()] {
+			let signer =
+				resolve_signer_with(&spec, CryptoScheme::Sr25519, dir.path(), || "pw".to_string());
+			assert_eq!(signer.public().unwrap(), info.public, "{spec}");
 		}
 	}
+
+	#[test]
+	fn signer_falls_back_to_secret_uris() {
+		let dir = tempfile::tempdir().unwrap();
+		let signer = resolve_signer_with("//Dave", CryptoScheme::Sr25519, dir.path(), || {
+			unreachable!("no keystore key is involved")
+		});
+		let expected = support::keyring::from_name("//Dave").unwrap().pair(CryptoScheme::Sr25519);
+		assert_eq!(signer.public().unwrap(), expected.unwrap().public());
+	}
 }
@@ -0,0 +1,167 @@
+use parity_scale_codec::{Decode, Encode};
+
+use crate::support::blake2_256;
+
+pub type Hash = [u8; 32];
+
+/// Root of a tree with no leaves.
+pub const EMPTY_ROOT: Hash = [0; 32];
+
+// Leaves and inner nodes are hashed under different tags, so an inner node can never be passed
+// off as a leaf.
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+/// The tree's hash of a leaf holding `data`.
+pub fn leaf_hash(data: &[u8]) -> Hash {
+	let mut tagged = Vec::with_capacity(data.len() + 1);
+	tagged.push(LEAF_TAG);
+	tagged.extend_from_slice(data);
+	blake2_256(&tagged)
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+	let mut tagged = [0; 65];
+	tagged[0] = NODE_TAG;
+	tagged[1..33].copy_from_slice(left);
+	tagged[33..].copy_from_slice(right);
+	blake2_256(&tagged)
+}
+
+/// The level above `level`: each pair hashed together, with an odd last node carried up as is.
+fn parent_level(level: &[Hash]) -> Vec<Hash> {
+	level
+		.chunks(2)
+		.map(|pair| match pair {
+			[left, right] => node_hash(left, right),
+			[single] => *single,
+			_ => unreachable!("chunks of two"),
+		})
+		.collect()
+}
+
+/// Root of the binary tree over `leaves`, which are `leaf_hash`es in tree order.
+pub fn root(leaves: &[Hash]) -> Hash {
+	MerkleTree::new(leaves.to_vec()).root()
+}
+
+/// Every level of the binary tree over some leaves, leaves first and root last, kept so the root
+/// and any number of proofs come without rehashing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+	levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+	/// Build the tree over `leaves`, which are `leaf_hash`es in tree order.
+	pub fn new(leaves: Vec<Hash>) -> Self {
+		let mut levels = vec![leaves];
+		while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+			levels.push(parent_level(level));
+		}
+		Self { levels }
+	}
+
+	pub fn root(&self) -> Hash {
+		self.levels
+			.last()
+			.and_then(|level| level.first())
+			.copied()
+			.unwrap_or(EMPTY_ROOT)
+	}
+
+	/// Proof for the leaf at `index`, or `None` if there is no such leaf.
+	pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+		let leaf_count = self.levels[0].len();
+		if index >= leaf_count {
+			return None;
+		}
+		let mut siblings = Vec::new();
+		let mut i = index;
+		for level in &self.levels[..self.levels.len() - 1] {
+			if let Some(sibling) = level.get(i ^ 1) {
+				siblings.push(*sibling);
+			}
+			i /= 2;
+		}
+		Some(MerkleProof { leaf_index: index as u32, leaf_count: leaf_count as u32, siblings })
+	}
+}
+
+/// The sibling hashes linking one leaf to the root, lowest first. A level where the leaf's node
+/// has no sibling contributes none.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct MerkleProof {
+	pub leaf_index: u32,
+	pub leaf_count: u32,
+	pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+	/// Proof for `leaves[index]`, or `None` if there is no such leaf. Build a `MerkleTree` instead
+	/// to prove several leaves of the same tree.
+	pub fn new(leaves: &[Hash], index: usize) -> Option<Self> {
+		MerkleTree::new(leaves.to_vec()).proof(index)
+	}
+
+	/// Whether `leaf` sits at `leaf_index` in a tree of `leaf_count` leaves with root `root`.
+	pub fn verify(&self, root: &Hash, leaf: &Hash) -> bool {
+		if self.leaf_index >= self.leaf_count {
+			return false;
+		}
+		let mut siblings = self.siblings.iter();
+		let (mut hash, mut i, mut len) = (*leaf, self.leaf_index, self.leaf_count);
+		while len > 1 {
+			if (i ^ 1) < len {
+				let Some(sibling) = siblings.next() else { return false };
+				hash =
+					if i % 2 == 0 { node_hash(&hash, sibling) } else { node_hash(sibling, &hash) };
+			}
+			i /= 2;
+			len = len.div_ceil(2);
+		}
+		siblings.next().is_none() && hash == *root
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn leaves(n: u8) -> Vec<Hash> {
+		(0..n).map(|i| leaf_hash(&[i])).collect()
+	}
+
+	#[test]
+	fn every_leaf_proves_against_the_root() {
+		for n in 1..=9 {
+			let leaves = leaves(n);
+			let root = root(&leaves);
+			for (i, leaf) in leaves.iter().enumerate() {
+				let proof = MerkleProof::new(&leaves, i).unwrap();
+				assert!(proof.verify(&root, leaf), "leaf {i} of {n}");
+			}
+		}
+		assert_eq!(root(&[]), EMPTY_ROOT);
+		assert_eq!(MerkleProof::new(&leaves(3), 3), None);
+	}
+
+	#[test]
+	fn tampered_proofs_fail() {
+		let leaves = leaves(5);
+		let root = root(&leaves);
+		let proof = MerkleProof::new(&leaves, 2).unwrap();
+		assert!(!proof.verify(&root, &leaves[3]), "wrong leaf");
+		assert!(!proof.verify(&leaf_hash(b"other"), &leaves[2]), "wrong root");
+
+		let mut moved = proof.clone();
+		moved.leaf_index = 3;
+		assert!(!moved.verify(&root, &leaves[2]), "wrong position");
+		let mut short = proof.clone();
+		short.siblings.pop();
+		assert!(!short.verify(&root, &leaves[2]), "missing sibling");
+		let mut long = proof;
+		long.siblings.push(leaves[0]);
+		assert!(!long.verify(&root, &leaves[2]), "extra sibling");
+	}
+}
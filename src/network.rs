@@ -1,10 +1,12 @@
 use libp2p::{
 	Swarm,
 	gossipsub::{self, IdentTopic},
+	identity::Keypair,
 	noise,
 	swarm::NetworkBehaviour,
 	tcp, yamux,
 };
+use std::{io::Write, path::Path};
 
 /// `#[derive(NetworkBehaviour)]` generates a `NodeBehaviourEvent::Gossipsub` variant
 /// used to pattern-match incoming gossip messages in the network loop.
@@ -21,8 +23,40 @@ pub fn block_topic() -> IdentTopic {
 	IdentTopic::new("blocks")
 }
 
-pub fn build_swarm() -> Result<Swarm<NodeBehaviour>, Box<dyn std::error::Error>> {
-	let swarm = libp2p::SwarmBuilder::with_new_identity()
+/// Ed25519 node key from a 32-byte secret given as hex (`0x` optional), as `--node-key` takes it.
+pub fn node_key_from_hex(hex: &str) -> Result<Keypair, String> {
+	let mut secret = hex::decode(hex.trim().trim_start_matches("0x"))
+		.map_err(|e| format!("invalid node key hex: {e}"))?;
+	Keypair::ed25519_from_bytes(&mut secret).map_err(|_| "node key must be 32 bytes".into())
+}
+
+/// Load the node key stored at `path`, or generate one and store it there, so the `PeerId` (and
+/// with it the authorship order) survives restarts. The file holds the hex secret.
+pub fn load_or_create_node_key(path: &Path) -> Result<Keypair, String> {
+	if path.exists() {
+		let hex = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read node key '{}': {e}", path.display()))?;
+		return node_key_from_hex(&hex);
+	}
+	let keypair = libp2p::identity::ed25519::Keypair::generate();
+	let secret = hex::encode(keypair.secret().as_ref());
+	if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		std::fs::create_dir_all(dir)
+			.map_err(|e| format!("failed to create '{}': {e}", dir.display()))?;
+	}
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+	options
+		.open(path)
+		.and_then(|mut file| file.write_all(secret.as_bytes()))
+		.map_err(|e| format!("failed to write node key '{}': {e}", path.display()))?;
+	Ok(keypair.into())
+}
+
+pub fn build_swarm(keypair: Keypair) -> Result<Swarm<NodeBehaviour>, Box<dyn std::error::Error>> {
+	let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
 		.with_tokio()
 		.with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)?
 		.with_behaviour(|key| {
@@ -42,3 +76,26 @@ pub fn build_swarm() -> Result<Swarm<NodeBehaviour>, Box<dyn std::error::Error>>
 		.build();
 	Ok(swarm)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn node_key_file_is_created_once_and_reloaded() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("network").join("node.key");
+		let created = load_or_create_node_key(&path).unwrap();
+		let loaded = load_or_create_node_key(&path).unwrap();
+		assert_eq!(created.public().to_peer_id(), loaded.public().to_peer_id());
+	}
+
+	#[test]
+	fn node_key_from_hex_is_deterministic() {
+		let hex = format!("0x{}", "11".repeat(32));
+		let a = node_key_from_hex(&hex).unwrap().public().to_peer_id();
+		let b = node_key_from_hex(&hex[2..]).unwrap().public().to_peer_id();
+		assert_eq!(a, b);
+		assert!(node_key_from_hex("1234").is_err());
+	}
+}
@@ -0,0 +1,308 @@
+use core::fmt::Debug;
+use parity_scale_codec::{Decode, Encode};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+	support::{DispatchResult, ensure_signed, load_prefix, write_prefix},
+	system::{self, OriginFor},
+};
+
+const PREFIX_COLLECTION: &[u8] = b"nfts:collection:";
+const PREFIX_ITEM: &[u8] = b"nfts:item:";
+
+pub trait Config: system::Config {
+	type CollectionId: Debug + Ord + Copy + Encode + Decode;
+	type ItemId: Debug + Ord + Copy + Encode + Decode;
+	/// Arbitrary data attached to an item at mint, stored SCALE-encoded.
+	type Attributes: Debug + Clone + Encode + Decode;
+	/// Largest encoded size of an item's attributes, in bytes.
+	const MAX_ATTRIBUTES_LEN: usize;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct CollectionDetails<AccountId> {
+	/// The creator; the only account allowed to mint into the collection.
+	pub owner: AccountId,
+	/// Items currently in the collection.
+	pub items: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ItemDetails<AccountId, Attributes> {
+	pub owner: AccountId,
+	/// Account allowed to transfer the item on the owner's behalf, until it changes hands.
+	pub approved: Option<AccountId>,
+	pub attributes: Attributes,
+}
+
+/// Items by `(collection, item)`.
+type ItemMap<CollectionId, ItemId, AccountId, Attributes> =
+	BTreeMap<(CollectionId, ItemId), ItemDetails<AccountId, Attributes>>;
+/// `(collection, item)` of each owner's items.
+type OwnerIndex<AccountId, CollectionId, ItemId> =
+	BTreeMap<AccountId, BTreeSet<(CollectionId, ItemId)>>;
+
+#[derive(Clone, Debug)]
+pub struct Pallet<T: Config> {
+	collections: BTreeMap<T::CollectionId, CollectionDetails<T::AccountId>>,
+	items: ItemMap<T::CollectionId, T::ItemId, T::AccountId, T::Attributes>,
+	/// Items by owner; rebuilt from `items` on start-up rather than stored.
+	owned: OwnerIndex<T::AccountId, T::CollectionId, T::ItemId>,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn new() -> Self {
+		let items: BTreeMap<_, ItemDetails<T::AccountId, T::Attributes>> = load_prefix(PREFIX_ITEM);
+		let mut owned: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+		for (key, details) in &items {
+			owned.entry(details.owner.clone()).or_default().insert(*key);
+		}
+		Self { collections: load_prefix(PREFIX_COLLECTION), items, owned }
+	}
+
+	pub fn collection(
+		&self,
+		collection: T::CollectionId,
+	) -> Option<&CollectionDetails<T::AccountId>> {
+		self.collections.get(&collection)
+	}
+
+	pub fn item(
+		&self,
+		collection: T::CollectionId,
+		item: T::ItemId,
+	) -> Option<&ItemDetails<T::AccountId, T::Attributes>> {
+		self.items.get(&(collection, item))
+	}
+
+	pub fn owner(&self, collection: T::CollectionId, item: T::ItemId) -> Option<&T::AccountId> {
+		self.item(collection, item).map(|details| &details.owner)
+	}
+
+	/// `(collection, item)` of every item `who` owns, in order.
+	pub fn items_of(&self, who: &T::AccountId) -> Vec<(T::CollectionId, T::ItemId)> {
+		self.owned
+			.get(who)
+			.map_or_else(Vec::new, |items| items.iter().copied().collect())
+	}
+
+	fn write_item(
+		&mut self,
+		collection: T::CollectionId,
+		item: T::ItemId,
+		details: Option<ItemDetails<T::AccountId, T::Attributes>>,
+	) {
+		if let Some(old) = self.items.get(&(collection, item)) {
+			let owned = self.owned.get_mut(&old.owner).expect("indexed on write");
+			owned.remove(&(collection, item));
+			if owned.is_empty() {
+				self.owned.remove(&old.owner);
+			}
+		}
+		if let Some(details) = &details {
+			self.owned.entry(details.owner.clone()).or_default().insert((collection, item));
+		}
+		write_prefix(&mut self.items, PREFIX_ITEM, (collection, item), details);
+	}
+
+	/// Change `collection`'s item count by one, up or down.
+	fn count_item(&mut self, collection: T::CollectionId, added: bool) {
+		let mut details = self.collections[&collection].clone();
+		details.items = if added { details.items + 1 } else { details.items - 1 };
+		write_prefix(&mut self.collections, PREFIX_COLLECTION, collection, Some(details));
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create an empty collection owned by the caller.
+	pub fn create_collection(
+		&mut self,
+		origin: OriginFor<T>,
+		collection: T::CollectionId,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if self.collections.contains_key(&collection) {
+			return Err("Collection id is already in use.");
+		}
+		let details = CollectionDetails { owner: caller, items: 0 };
+		write_prefix(&mut self.collections, PREFIX_COLLECTION, collection, Some(details));
+		Ok(())
+	}
+
+	/// Collection owner only: create `item` in `collection`, owned by `owner`.
+	pub fn mint(
+		&mut self,
+		origin: OriginFor<T>,
+		collection: T::CollectionId,
+		item: T::ItemId,
+		owner: T::AccountId,
+		attributes: T::Attributes,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let details = self.collections.get(&collection).ok_or("Unknown collection.")?;
+		if details.owner != caller {
+			return Err("Caller is not the collection owner.");
+		}
+		if self.items.contains_key(&(collection, item)) {
+			return Err("Item already exists.");
+		}
+		if attributes.encoded_size() > T::MAX_ATTRIBUTES_LEN {
+			return Err("Item attributes are too large.");
+		}
+
+		self.write_item(collection, item, Some(ItemDetails { owner, approved: None, attributes }));
+		self.count_item(collection, true);
+		Ok(())
+	}
+
+	/// Move an item to `to`. The caller must be its owner or approved delegate; the approval is
+	/// cleared either way.
+	pub fn transfer(
+		&mut self,
+		origin: OriginFor<T>,
+		collection: T::CollectionId,
+		item: T::ItemId,
+		to: T::AccountId,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut details = self.item(collection, item).ok_or("Unknown item.")?.clone();
+		if details.owner != caller && details.approved.as_ref() != Some(&caller) {
+			return Err("Caller is neither the owner nor approved.");
+		}
+		details.owner = to;
+		details.approved = None;
+		self.write_item(collection, item, Some(details));
+		Ok(())
+	}
+
+	/// Owner only: destroy an item.
+	pub fn burn(
+		&mut self,
+		origin: OriginFor<T>,
+		collection: T::CollectionId,
+		item: T::ItemId,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if self.owner(collection, item).ok_or("Unknown item.")? != &caller {
+			return Err("Caller is not the item owner.");
+		}
+		self.write_item(collection, item, None);
+		self.count_item(collection, false);
+		Ok(())
+	}
+
+	/// Owner only: let `delegate` transfer the item, replacing any earlier delegate. `None`
+	/// cancels the approval.
+	pub fn approve(
+		&mut self,
+		origin: OriginFor<T>,
+		collection: T::CollectionId,
+		item: T::ItemId,
+		delegate: Option<T::AccountId>,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut details = self.item(collection, item).ok_or("Unknown item.")?.clone();
+		if details.owner != caller {
+			return Err("Caller is not the item owner.");
+		}
+		details.approved = delegate;
+		self.write_item(collection, item, Some(details));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::support::RuntimeOrigin;
+
+	struct TestConfig;
+	impl system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+	}
+	impl Config for TestConfig {
+		type CollectionId = u32;
+		type ItemId = u32;
+		type Attributes = Vec<(String, String)>;
+		const MAX_ATTRIBUTES_LEN: usize = 32;
+	}
+
+	fn attrs(value: &str) -> Vec<(String, String)> {
+		vec![("title".to_string(), value.to_string())]
+	}
+
+	/// Collection 1, owned by alice, with items 1 and 2 minted to alice.
+	fn with_items() -> Pallet<TestConfig> {
+		let mut p = Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		p.create_collection(RuntimeOrigin::Signed(alice.clone()), 1).unwrap();
+		p.mint(RuntimeOrigin::Signed(alice.clone()), 1, 1, alice.clone(), attrs("one"))
+			.unwrap();
+		p.mint(RuntimeOrigin::Signed(alice.clone()), 1, 2, alice, attrs("two")).unwrap();
+		p
+	}
+
+	#[test]
+	fn mint_is_for_the_collection_owner() {
+		let mut p = with_items();
+		let bob = "bob".to_string();
+		assert_eq!(p.collection(1).unwrap().items, 2);
+		assert_eq!(p.item(1, 2).unwrap().attributes, attrs("two"));
+		let err = p.mint(RuntimeOrigin::Signed(bob.clone()), 1, 3, bob.clone(), attrs("three"));
+		assert_eq!(err, Err("Caller is not the collection owner."));
+		let err =
+			p.mint(RuntimeOrigin::Signed("alice".to_string()), 1, 1, bob.clone(), attrs("dup"));
+		assert_eq!(err, Err("Item already exists."));
+		let too_big = attrs(&"x".repeat(32));
+		assert!(
+			p.mint(RuntimeOrigin::Signed("alice".to_string()), 1, 3, bob.clone(), too_big)
+				.is_err()
+		);
+		assert_eq!(
+			p.create_collection(RuntimeOrigin::Signed(bob), 1),
+			Err("Collection id is already in use.")
+		);
+	}
+
+	#[test]
+	fn transfer_by_owner_or_delegate_updates_the_owner_index() {
+		let mut p = with_items();
+		let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+		assert!(p.transfer(RuntimeOrigin::Signed(bob.clone()), 1, 1, bob.clone()).is_err());
+		p.approve(RuntimeOrigin::Signed(alice.clone()), 1, 1, Some(bob.clone()))
+			.unwrap();
+		p.transfer(RuntimeOrigin::Signed(bob.clone()), 1, 1, carol.clone()).unwrap();
+		assert_eq!(p.owner(1, 1), Some(&carol));
+		assert_eq!(p.item(1, 1).unwrap().approved, None, "approval cleared on transfer");
+		assert!(p.transfer(RuntimeOrigin::Signed(bob), 1, 1, alice.clone()).is_err());
+
+		assert_eq!(p.items_of(&alice), [(1, 2)]);
+		assert_eq!(p.items_of(&carol), [(1, 1)]);
+	}
+
+	#[test]
+	fn burn_removes_the_item() {
+		let mut p = with_items();
+		let alice = "alice".to_string();
+		assert!(p.burn(RuntimeOrigin::Signed("bob".to_string()), 1, 1).is_err());
+		p.burn(RuntimeOrigin::Signed(alice.clone()), 1, 1).unwrap();
+		assert_eq!(p.owner(1, 1), None);
+		assert_eq!(p.collection(1).unwrap().items, 1);
+		assert_eq!(p.items_of(&alice), [(1, 2)]);
+		assert_eq!(p.burn(RuntimeOrigin::Signed(alice), 1, 1), Err("Unknown item."));
+	}
+
+	#[test]
+	fn state_and_owner_index_survive_reload() {
+		let mut p = with_items();
+		p.transfer(RuntimeOrigin::Signed("alice".to_string()), 1, 2, "bob".to_string())
+			.unwrap();
+		let reloaded = Pallet::<TestConfig>::new();
+		assert_eq!(reloaded.collection(1), p.collection(1));
+		assert_eq!(reloaded.item(1, 1), p.item(1, 1));
+		assert_eq!(reloaded.items_of(&"bob".to_string()), [(1, 2)]);
+	}
+}
@@ -1,15 +1,18 @@
-use std::sync::Arc;
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
 
 use axum::{
 	Router,
 	body::Bytes,
-	extract::{Path, State},
+	extract::{Path, Query, State},
 	http::StatusCode,
 	routing::{get, post},
 };
 use futures::StreamExt;
 use libp2p::{Multiaddr, PeerId, gossipsub, swarm::SwarmEvent};
-use parity_scale_codec::{Decode, Encode};
+use parity_scale_codec::{Decode, DecodeLimit, Encode};
 use tokio::{
 	sync::{Mutex, RwLock, mpsc},
 	time,
@@ -62,38 +65,63 @@ struct RpcState {
 	ext_topic_hash: gossipsub::TopicHash,
 }
 
-/// `POST /submit` — body is a raw SCALE-encoded extrinsic.
+/// `POST /submit` — body is a raw SCALE-encoded extrinsic. Responds with its `0x`-prefixed hash,
+/// which can be passed to `GET /extrinsic/:hash`.
 async fn submit_handler(
 	State(s): State<RpcState>,
 	body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<(StatusCode, String), (StatusCode, String)> {
 	let raw = body.to_vec();
 
-	let ext = types::Extrinsic::decode(&mut &raw[..])
-		.map_err(|e| (StatusCode::BAD_REQUEST, format!("SCALE decode failed: {e}")))?;
+	let ext =
+		types::Extrinsic::decode_with_depth_limit(support::MAX_EXTRINSIC_DEPTH, &mut &raw[..])
+			.map_err(|e| (StatusCode::BAD_REQUEST, format!("SCALE decode failed: {e}")))?;
+	if ext.valid_until < support::now_millis() {
+		return Err((StatusCode::BAD_REQUEST, "extrinsic has expired".into()));
+	}
+	let hash = ext.hash();
 
 	// Gossip to peers so the designated slot author can include the tx even if it wasn't
 	// submitted directly to them.
 	let _ = s.tx_pub.send(PublishReq { topic: s.ext_topic_hash, data: raw });
 	let _ = s.tx_ext.send(ext);
 
-	Ok(StatusCode::ACCEPTED)
+	Ok((StatusCode::ACCEPTED, format!("0x{}", hex::encode(hash))))
 }
 
-/// `GET /nonce/<hex_pubkey>` — returns `runtime_nonce + pending_mempool_count`,
-/// This lets a client submit multiple txs in rapid succession with correct sequential nonces.
-async fn nonce_handler(
+/// `GET /extrinsic/<hex_hash>` — the decoded extrinsic with its inclusion block, index and
+/// outcome, or `pending` if it is still in the mempool.
+async fn extrinsic_handler(
 	State(s): State<RpcState>,
 	Path(hex): Path<String>,
 ) -> Result<String, (StatusCode, String)> {
-	let bytes =
-		hex::decode(&hex).map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid hex: {e}")))?;
-	if bytes.len() != 32 {
-		return Err((StatusCode::BAD_REQUEST, "account must be 32 bytes".into()));
+	let hash = support::parse_hash(&hex).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+	if let Some(record) = support::extrinsic_record::<types::BlockNumber>(&hash) {
+		let ext = types::Extrinsic::decode(&mut &record.extrinsic[..]).map_err(|e| {
+			(StatusCode::INTERNAL_SERVER_ERROR, format!("stored extrinsic is corrupt: {e}"))
+		})?;
+		return Ok(format!(
+			"{ext:#?}\nblock: {}\nindex: {}\noutcome: {:?}",
+			record.block_number, record.index, record.outcome
+		));
 	}
-	let mut arr = [0u8; 32];
-	arr.copy_from_slice(&bytes);
-	let account = crate::support::AccountId32(arr);
+
+	let mempool = s.mempool.lock().await;
+	match mempool.pending_extrinsics().find(|e| e.hash() == hash) {
+		Some(ext) => Ok(format!("{ext:#?}\nstatus: pending")),
+		None => Err((StatusCode::NOT_FOUND, "extrinsic not found".into())),
+	}
+}
+
+/// `GET /nonce/<account>` (SS58 address or hex) — returns `runtime_nonce + pending_mempool_count`,
+/// This lets a client submit multiple txs in rapid succession with correct sequential nonces.
+async fn nonce_handler(
+	State(s): State<RpcState>,
+	Path(account): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+	let account: crate::support::AccountId32 =
+		account.parse().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
 	let base = s.runtime.read().await.system.nonce(&account);
 	let pending = s
@@ -106,6 +134,80 @@ async fn nonce_handler(
 	Ok((base + pending).to_string())
 }
 
+/// `GET /claim/<hex_digest>` — the proof-of-existence claim on the content with that `blake2_256`
+/// digest: owner, delegate, block, timestamp and metadata, then every owner it has had.
+async fn claim_handler(
+	State(s): State<RpcState>,
+	Path(content): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+	let content = support::parse_hash(&content).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+	let rt = s.runtime.read().await;
+	let info = rt
+		.proof_of_existence
+		.get_claim(&content)
+		.ok_or((StatusCode::NOT_FOUND, "claim not found".to_string()))?;
+	let history: String = rt
+		.proof_of_existence
+		.history(&content)
+		.iter()
+		.map(|r| format!("\n  {r}"))
+		.collect();
+	Ok(format!("{info}\nhistory:{history}"))
+}
+
+/// Most claims `GET /claims/:account` returns per page.
+const MAX_CLAIMS_PAGE: usize = 100;
+
+/// `GET /claims/<account>?start=<n>&limit=<n>` (SS58 address or hex) — `total: <count>`, then the
+/// `0x` digest of each claim the account owns, in digest order. `start` skips that many claims
+/// and `limit` (at most 100, the default) caps how many follow.
+async fn claims_handler(
+	State(s): State<RpcState>,
+	Path(account): Path<String>,
+	Query(params): Query<HashMap<String, String>>,
+) -> Result<String, (StatusCode, String)> {
+	let account: crate::support::AccountId32 =
+		account.parse().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+	let param = |name: &str, default: usize| {
+		params.get(name).map_or(Ok(default), |value| {
+			value
+				.parse::<usize>()
+				.map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid {name}: {e}")))
+		})
+	};
+	let start = param("start", 0)?;
+	let limit = param("limit", MAX_CLAIMS_PAGE)?.min(MAX_CLAIMS_PAGE);
+
+	let claims = s.runtime.read().await.proof_of_existence.claims_of(&account);
+	let page: String = claims
+		.iter()
+		.skip(start)
+		.take(limit)
+		.map(|claim| format!("\n0x{}", hex::encode(claim)))
+		.collect();
+	Ok(format!("total: {}{page}", claims.len()))
+}
+
+/// `GET /claim/<hex_digest>/proof` — the latest block's header and the claim on that digest with
+/// its Merkle proof against the header's state root, each as `0x`-prefixed SCALE. Check them with
+/// `proof_of_existence::verify_proof` or the `verify-proof` command.
+async fn claim_proof_handler(
+	State(s): State<RpcState>,
+	Path(content): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+	let content = support::parse_hash(&content).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+	let rt = s.runtime.read().await;
+	let proof = rt
+		.proof_of_existence
+		.prove_claim(&content)
+		.ok_or((StatusCode::NOT_FOUND, "claim not found".to_string()))?;
+	Ok(format!(
+		"header: 0x{}\nproof: 0x{}",
+		hex::encode(rt.system.header().encode()),
+		hex::encode(proof.encode())
+	))
+}
+
 /// `GET /state` — returns the full runtime debug dump as plain text.
 async fn state_handler(State(s): State<RpcState>) -> String {
 	let rt = s.runtime.read().await;
@@ -124,6 +226,10 @@ async fn start_rpc_server(
 	let app = Router::new()
 		.route("/submit", post(submit_handler))
 		.route("/nonce/:account", get(nonce_handler))
+		.route("/extrinsic/:hash", get(extrinsic_handler))
+		.route("/claim/:content", get(claim_handler))
+		.route("/claim/:content/proof", get(claim_proof_handler))
+		.route("/claims/:account", get(claims_handler))
 		.route("/state", get(state_handler))
 		.with_state(state);
 
@@ -141,6 +247,7 @@ pub async fn run(
 	port: u16,
 	dial_addr: Option<Multiaddr>,
 	rpc_port: Option<u16>,
+	node_key: libp2p::identity::Keypair,
 ) -> Result<(), Box<dyn std::error::Error>> {
 	let runtime: SharedRuntime = {
 		let mut rt = crate::Runtime::new();
@@ -149,7 +256,7 @@ pub async fn run(
 	};
 	let mempool: SharedMempool = Arc::new(Mutex::new(types::Mempool::with_block_limit(3)));
 
-	let mut swarm = network::build_swarm()?;
+	let mut swarm = network::build_swarm(node_key)?;
 
 	let my_peer_id = swarm.local_peer_id().clone();
 
@@ -207,15 +314,22 @@ pub async fn run(
 		loop {
 			tokio::select! {
 				Some(ext) = rx_ext.recv() => {
+					// Check the signature once on admission; block import then skips it via the
+					// verified-extrinsic cache.
+					if let Err(e) = ext.verify() {
+						eprintln!("[node] dropping extrinsic: {e}");
+						continue;
+					}
+					support::verified_cache().lock().expect("verified cache poisoned").insert(&ext);
 					// Accumulate in mempool — the slot author seals all pending txs at once.
 					let mut pool = mp_app.lock().await;
 					let _ = pool.submit(ext);
 				}
 
 				Some(block) = rx_blk.recv() => {
-					// Snapshot (signer, nonce) pairs before block is moved into execute_block.
-					let included: Vec<(support::AccountId32, u32)> =
-						block.extrinsics.iter().map(|e| (e.signer, e.nonce)).collect();
+					// Snapshot the extrinsic hashes before block is moved into execute_block.
+					let included: HashSet<[u8; 32]> =
+						block.extrinsics.iter().map(|e| e.hash()).collect();
 					let applied = {
 						let mut rt = rt_app.write().await;
 						match rt.execute_block(block) {
@@ -232,7 +346,7 @@ pub async fn run(
 					// Evict the included txs so we don't seal a duplicate block next slot.
 					if applied {
 						let mut mp = mp_app.lock().await;
-						mp.retain(|e| !included.iter().any(|(s, n)| *s == e.signer && *n == e.nonce));
+						mp.retain(|e| !included.contains(&e.hash()));
 					}
 				}
 
@@ -258,18 +372,19 @@ pub async fn run(
 			event = swarm.select_next_some() => {
 				match event {
 					SwarmEvent::NewListenAddr { address, .. } => {
-						println!("[net] listening on {address}");
+						println!("[net] listening on {address}/p2p/{my_peer_id}");
 					}
 					SwarmEvent::Behaviour(network::NodeBehaviourEvent::Gossipsub(
 						gossipsub::Event::Message { message, .. },
 					)) => {
+						let (depth, data) = (support::MAX_EXTRINSIC_DEPTH, &mut &message.data[..]);
 						if message.topic == ext_hash {
-							match types::Extrinsic::decode(&mut &message.data[..]) {
+							match types::Extrinsic::decode_with_depth_limit(depth, data) {
 								Ok(ext) => { let _ = tx_ext.send(ext); }
 								Err(e) => eprintln!("[net] bad extrinsic bytes: {e}"),
 							}
 						} else if message.topic == blk_hash {
-							match types::Block::decode(&mut &message.data[..]) {
+							match types::Block::decode_with_depth_limit(depth, data) {
 								Ok(blk) => { let _ = tx_blk.send(blk); }
 								Err(e) => eprintln!("[net] bad block bytes: {e}"),
 							}
@@ -343,17 +458,23 @@ async fn produce_block(
 
 	let mut rt = runtime.write().await;
 	let next_num = rt.system.block_number().checked_add(1u32).unwrap();
-	let block =
-		types::Block { header: support::Header { block_number: next_num }, extrinsics: batch };
+	// Clamped to the parent's timestamp so a clock behind the previous author's still produces a
+	// valid block.
+	let timestamp = support::now_millis().max(rt.system.timestamp());
+	// The state root is filled in by `author_block` once the block has run.
+	let block = types::Block {
+		header: support::Header { block_number: next_num, timestamp, state_root: [0; 32] },
+		extrinsics: batch,
+	};
 
-	let encoded = block.encode();
 	let tx_summary: Vec<String> = block
 		.extrinsics
 		.iter()
 		.map(|e| format!("    signer={:?} nonce={}", e.signer, e.nonce))
 		.collect();
-	match rt.execute_block(block) {
-		Ok(()) => {
+	match rt.author_block(block) {
+		Ok(block) => {
+			let encoded = block.encode();
 			println!("[node] produced block #{next_num} ({} tx)", tx_summary.len());
 			for line in &tx_summary {
 				println!("{line}");
@@ -1,80 +1,488 @@
-use crate::support::{DispatchResult, KeyValueStore, kv_store};
-use core::fmt::Debug;
+use crate::{
+	merkle::{self, MerkleProof},
+	support::{
+		BalanceStatus, Currency, DispatchResult, Header, ensure_root, ensure_signed, load_prefix,
+		write_prefix,
+	},
+	system::{self, OriginFor},
+};
+use core::fmt::{self, Debug};
+use num::traits::Zero;
 use parity_scale_codec::{Decode, Encode};
-use std::collections::BTreeMap;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	sync::OnceLock,
+};
 
 const PREFIX_POE: &[u8] = b"poe:";
+// Not under `poe:`, whose scan would pick these entries up as claims.
+const PREFIX_HISTORY: &[u8] = b"poe_history:";
 
-pub trait Config: crate::system::Config {
-	type Content: Debug + Ord + Encode + Decode;
+pub trait Config: system::Config {
+	type Content: Debug + Ord + Clone + Encode + Decode;
+	/// Free-form description the claimer attaches to a claim, stored SCALE-encoded.
+	type Metadata: Debug + Clone + Encode + Decode;
+	type Balance: Debug + Copy + Eq + Encode + Decode;
+	/// Where claim deposits are reserved; `balances::Pallet` in the runtime.
+	type Currency: Currency<Self::AccountId, Balance = Self::Balance, BlockNumber = Self::BlockNumber>;
+	/// Reserved from the owner for as long as a claim exists.
+	const CLAIM_DEPOSIT: Self::Balance;
+	/// Largest encoded size of a claim's metadata, in bytes.
+	const MAX_METADATA_LEN: usize;
+	/// Most claims one account can own at once.
+	const MAX_CLAIMS_PER_ACCOUNT: u32;
+	/// Most expired claims removed at the start of one block; any beyond wait for the next.
+	const MAX_EXPIRIES_PER_BLOCK: u32;
 }
 
-#[derive(Debug)]
+/// Who holds a piece of content, and when it was first claimed.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ClaimInfo<AccountId, BlockNumber, Balance, Metadata> {
+	pub owner: AccountId,
+	/// Held in the owner's reserved balance until the claim is revoked; it moves with the claim.
+	pub deposit: Balance,
+	/// Account allowed to revoke or transfer the claim on the owner's behalf, until it changes
+	/// hands.
+	pub delegate: Option<AccountId>,
+	/// Block the claim was created in.
+	pub block_number: BlockNumber,
+	/// Timestamp of that block, in milliseconds since the Unix epoch.
+	pub created_at: u64,
+	/// Block at whose start the claim is removed, if it is not renewed first. `None` never
+	/// expires.
+	pub expires_at: Option<BlockNumber>,
+	pub metadata: Metadata,
+}
+
+/// One `key: value` line per field, as served by the RPC `GET /claim/:content`.
+impl<AccountId, BlockNumber, Balance, Metadata> fmt::Display
+	for ClaimInfo<AccountId, BlockNumber, Balance, Metadata>
+where
+	AccountId: fmt::Display,
+	BlockNumber: fmt::Display,
+	Balance: fmt::Display,
+	Metadata: Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "owner: {}\ndelegate: ", self.owner)?;
+		match &self.delegate {
+			Some(delegate) => write!(f, "{delegate}")?,
+			None => write!(f, "none")?,
+		}
+		write!(
+			f,
+			"\ndeposit: {}\nblock: {}\ncreated_at: {}\nexpires_at: ",
+			self.deposit, self.block_number, self.created_at
+		)?;
+		match &self.expires_at {
+			Some(block) => write!(f, "{block}")?,
+			None => write!(f, "never")?,
+		}
+		write!(f, "\nmetadata: {:?}", self.metadata)
+	}
+}
+
+/// One link in a claim's chain of ownership: `owner` got the claim in `block_number`, by creating
+/// or receiving it.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct OwnershipRecord<AccountId, BlockNumber> {
+	pub owner: AccountId,
+	pub block_number: BlockNumber,
+	/// Timestamp of that block, in milliseconds since the Unix epoch.
+	pub timestamp: u64,
+}
+
+impl<AccountId: fmt::Display, BlockNumber: fmt::Display> fmt::Display
+	for OwnershipRecord<AccountId, BlockNumber>
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "block {} ({}): {}", self.block_number, self.timestamp, self.owner)
+	}
+}
+
+/// A claim and the Merkle proof that it is part of a block's state root, so anyone holding the
+/// block's header can check it without the chain's storage.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ClaimProof<Content, AccountId, BlockNumber, Balance, Metadata> {
+	pub claim: Content,
+	pub info: ClaimInfo<AccountId, BlockNumber, Balance, Metadata>,
+	pub proof: MerkleProof,
+}
+
+/// Leaf of the claims tree for `claim`.
+fn claim_leaf(claim: &impl Encode, info: &impl Encode) -> merkle::Hash {
+	merkle::leaf_hash(&(claim, info).encode())
+}
+
+/// Whether `proof` shows its claim in the state `header` commits to. Needs no storage, so it
+/// works for anyone who got the header from a source they trust.
+pub fn verify_proof<Content, AccountId, BlockNumber, Balance, Metadata, HeaderBlockNumber>(
+	header: &Header<HeaderBlockNumber>,
+	proof: &ClaimProof<Content, AccountId, BlockNumber, Balance, Metadata>,
+) -> bool
+where
+	ClaimInfo<AccountId, BlockNumber, Balance, Metadata>: Encode,
+	Content: Encode,
+{
+	proof.proof.verify(&header.state_root, &claim_leaf(&proof.claim, &proof.info))
+}
+
+pub type ClaimProofOf<T> = ClaimProof<
+	<T as Config>::Content,
+	<T as system::Config>::AccountId,
+	<T as system::Config>::BlockNumber,
+	<T as Config>::Balance,
+	<T as Config>::Metadata,
+>;
+type ClaimInfoOf<T> = ClaimInfo<
+	<T as system::Config>::AccountId,
+	<T as system::Config>::BlockNumber,
+	<T as Config>::Balance,
+	<T as Config>::Metadata,
+>;
+type Claims<Content, AccountId, BlockNumber, Balance, Metadata> =
+	BTreeMap<Content, ClaimInfo<AccountId, BlockNumber, Balance, Metadata>>;
+type History<Content, AccountId, BlockNumber> =
+	BTreeMap<Content, Vec<OwnershipRecord<AccountId, BlockNumber>>>;
+/// Content of each owner's claims.
+type OwnerIndex<AccountId, Content> = BTreeMap<AccountId, BTreeSet<Content>>;
+
+/// The claims tree, with the content at each leaf so a proof finds its leaf by binary search.
+#[derive(Clone, Debug)]
+struct ClaimsTree<Content> {
+	contents: Vec<Content>,
+	tree: merkle::MerkleTree,
+}
+
+#[derive(Clone, Debug)]
 pub struct Pallet<T: Config> {
-	claims: BTreeMap<T::Content, T::AccountId>,
+	#[allow(clippy::type_complexity)]
+	claims: Claims<T::Content, T::AccountId, T::BlockNumber, T::Balance, T::Metadata>,
+	/// Claims by owner; rebuilt from `claims` on start-up rather than stored.
+	owned: OwnerIndex<T::AccountId, T::Content>,
+	/// `(expiry block, content)` of every claim that expires, soonest first; rebuilt from `claims`
+	/// on start-up.
+	expiries: BTreeSet<(T::BlockNumber, T::Content)>,
+	/// Owners of each live claim, oldest first; dropped with the claim on revocation.
+	history: History<T::Content, T::AccountId, T::BlockNumber>,
+	/// Leaf hash of each claim, updated as claims change; rebuilt from `claims` on start-up.
+	leaves: BTreeMap<T::Content, merkle::Hash>,
+	/// Tree over `leaves`, built on first use and dropped whenever a claim changes.
+	tree: OnceLock<ClaimsTree<T::Content>>,
+	/// Current block number and timestamp, set by `on_initialize`.
+	now: T::BlockNumber,
+	timestamp: u64,
 }
 
 impl<T: Config> Pallet<T> {
 	pub fn new() -> Self {
-		let store = kv_store();
-		let mut claims = BTreeMap::new();
-
-		for (key, value) in store.scan_prefix(PREFIX_POE) {
-			if key.len() <= PREFIX_POE.len() {
-				continue;
-			}
-			let content_bytes = &key[PREFIX_POE.len()..];
-			if let (Ok(content), Ok(owner)) =
-				(T::Content::decode(&mut &content_bytes[..]), T::AccountId::decode(&mut &value[..]))
-			{
-				claims.insert(content, owner);
+		let claims: BTreeMap<T::Content, ClaimInfoOf<T>> = load_prefix(PREFIX_POE);
+		let mut owned: OwnerIndex<_, _> = BTreeMap::new();
+		let mut expiries = BTreeSet::new();
+		let leaves = claims.iter().map(|(claim, info)| (claim.clone(), claim_leaf(claim, info)));
+		let leaves = leaves.collect();
+		for (claim, info) in &claims {
+			owned.entry(info.owner.clone()).or_default().insert(claim.clone());
+			if let Some(block) = info.expires_at {
+				expiries.insert((block, claim.clone()));
 			}
 		}
+		Self {
+			claims,
+			owned,
+			expiries,
+			history: load_prefix(PREFIX_HISTORY),
+			leaves,
+			tree: OnceLock::new(),
+			now: T::BlockNumber::zero(),
+			timestamp: 0,
+		}
+	}
+
+	/// Called by the runtime at the start of every block with the `system` block number and
+	/// timestamp, which new claims are stamped with.
+	pub fn on_initialize(&mut self, now: T::BlockNumber, timestamp: u64) {
+		self.now = now;
+		self.timestamp = timestamp;
+	}
+
+	/// Remove up to `Config::MAX_EXPIRIES_PER_BLOCK` claims whose expiry block has been reached,
+	/// soonest first, returning their deposits. Called by the runtime after `on_initialize`.
+	pub fn remove_expired(&mut self, currency: &mut T::Currency) {
+		let expired: Vec<_> = self
+			.expiries
+			.iter()
+			.take_while(|(block, _)| *block <= self.now)
+			.take(T::MAX_EXPIRIES_PER_BLOCK as usize)
+			.map(|(_, claim)| claim.clone())
+			.collect();
+		for claim in expired {
+			let info = self.claims[&claim].clone();
+			self.remove_claim(currency, claim, &info);
+		}
+	}
+
+	/// The claim on `claim`, unless it has expired. An expired claim may still be stored, waiting
+	/// for `remove_expired`.
+	pub fn get_claim(&self, claim: &T::Content) -> Option<&ClaimInfoOf<T>> {
+		self.claims.get(claim).filter(|info| !self.is_expired(info))
+	}
+
+	/// Whether `info`'s expiry block has been reached.
+	fn is_expired(&self, info: &ClaimInfoOf<T>) -> bool {
+		info.expires_at.is_some_and(|block| block <= self.now)
+	}
+
+	/// Merkle root over every claim, in content order; what `Header::state_root` commits to.
+	pub fn state_root(&self) -> merkle::Hash {
+		self.tree().tree.root()
+	}
+
+	/// `claim` and the proof that it is part of `state_root`, if it exists and has not expired.
+	pub fn prove_claim(&self, claim: &T::Content) -> Option<ClaimProofOf<T>> {
+		let info = self.get_claim(claim)?.clone();
+		let tree = self.tree();
+		let index = tree.contents.binary_search(claim).ok()?;
+		let proof = tree.tree.proof(index)?;
+		Some(ClaimProof { claim: claim.clone(), info, proof })
+	}
+
+	/// The claims tree, built from the cached leaf hashes if a claim changed since the last call.
+	fn tree(&self) -> &ClaimsTree<T::Content> {
+		self.tree.get_or_init(|| ClaimsTree {
+			contents: self.leaves.keys().cloned().collect(),
+			tree: merkle::MerkleTree::new(self.leaves.values().copied().collect()),
+		})
+	}
+
+	/// Store `claim`'s info, or delete the claim with `None`, keeping its leaf hash in step.
+	fn write_claim(&mut self, claim: T::Content, info: Option<ClaimInfoOf<T>>) {
+		match &info {
+			Some(info) => self.leaves.insert(claim.clone(), claim_leaf(&claim, info)),
+			None => self.leaves.remove(&claim),
+		};
+		self.tree.take();
+		write_prefix(&mut self.claims, PREFIX_POE, claim, info);
+	}
+
+	/// How many claims `who` owns.
+	pub fn claim_count(&self, who: &T::AccountId) -> u32 {
+		self.owned.get(who).map_or(0, |claims| claims.len() as u32)
+	}
+
+	/// Content of every claim `who` owns, in order.
+	pub fn claims_of(&self, who: &T::AccountId) -> Vec<T::Content> {
+		self.owned
+			.get(who)
+			.map_or_else(Vec::new, |claims| claims.iter().cloned().collect())
+	}
+
+	/// Every owner `claim` has had, from its creator to its current owner.
+	pub fn history(&self, claim: &T::Content) -> &[OwnershipRecord<T::AccountId, T::BlockNumber>] {
+		self.history.get(claim).map_or(&[], Vec::as_slice)
+	}
+
+	/// The claim on `claim`, if `caller` is its owner or delegate.
+	fn ensure_owner_or_delegate(
+		&self,
+		caller: &T::AccountId,
+		claim: &T::Content,
+	) -> Result<&ClaimInfoOf<T>, &'static str> {
+		let info = self.claims.get(claim).ok_or("claim does not exist")?;
+		if info.owner != *caller && info.delegate.as_ref() != Some(caller) {
+			return Err("caller is not owner or delegate");
+		}
+		Ok(info)
+	}
 
-		Self { claims }
+	fn ensure_below_claim_cap(&self, who: &T::AccountId) -> DispatchResult {
+		if self.claim_count(who) >= T::MAX_CLAIMS_PER_ACCOUNT {
+			return Err("account owns too many claims");
+		}
+		Ok(())
 	}
 
-	fn claim_key(claim: &T::Content) -> Vec<u8> {
-		let mut key = PREFIX_POE.to_vec();
-		key.extend(claim.encode());
-		key
+	/// Add `claim` to `who`'s entry in the owner index, or remove it.
+	fn index_claim(&mut self, who: &T::AccountId, claim: &T::Content, added: bool) {
+		if added {
+			self.owned.entry(who.clone()).or_default().insert(claim.clone());
+			return;
+		}
+		let owned = self.owned.get_mut(who).expect("indexed on create");
+		owned.remove(claim);
+		if owned.is_empty() {
+			self.owned.remove(who);
+		}
 	}
 
-	#[allow(dead_code)]
-	pub fn get_claim(&self, claim: &T::Content) -> Option<&T::AccountId> {
-		self.claims.get(claim)
+	/// Delete `claim` and its history, returning the deposit to its owner.
+	fn remove_claim(
+		&mut self,
+		currency: &mut T::Currency,
+		claim: T::Content,
+		info: &ClaimInfoOf<T>,
+	) {
+		currency.unreserve(&info.owner, info.deposit);
+		self.index_claim(&info.owner, &claim, false);
+		if let Some(block) = info.expires_at {
+			self.expiries.remove(&(block, claim.clone()));
+		}
+		self.write_claim(claim.clone(), None);
+		write_prefix(&mut self.history, PREFIX_HISTORY, claim, None);
+	}
+
+	/// Append `owner` to `claim`'s history as of the current block.
+	fn record_owner(&mut self, claim: T::Content, owner: T::AccountId) {
+		let mut history = self.history.get(&claim).cloned().unwrap_or_default();
+		history.push(OwnershipRecord { owner, block_number: self.now, timestamp: self.timestamp });
+		write_prefix(&mut self.history, PREFIX_HISTORY, claim, Some(history));
 	}
 }
 
 #[macros::call]
 impl<T: Config> Pallet<T> {
-	pub fn create_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		if self.claims.contains_key(&claim) {
-			return Err(&"this content is already claimed");
+	/// Claim `claim` for the caller, stamped with the current block and `metadata`, until block
+	/// `expires_at` if one is given. Reserves `Config::CLAIM_DEPOSIT` from the caller. An expired
+	/// claim on the same content that `remove_expired` has not reached yet is removed first.
+	pub fn create_claim(
+		&mut self,
+		origin: OriginFor<T>,
+		currency: &mut T::Currency,
+		claim: T::Content,
+		metadata: T::Metadata,
+		expires_at: Option<T::BlockNumber>,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if self.get_claim(&claim).is_some() {
+			return Err("this content is already claimed");
+		}
+		if metadata.encoded_size() > T::MAX_METADATA_LEN {
+			return Err("claim metadata is too large");
+		}
+		if expires_at.is_some_and(|block| block <= self.now) {
+			return Err("claim expiry is not in the future");
 		}
-		self.claims.insert(claim, caller);
+		// Due for removal anyway, so it goes even if the new claim then fails.
+		if let Some(expired) = self.claims.get(&claim).cloned() {
+			self.remove_claim(currency, claim.clone(), &expired);
+		}
+		self.ensure_below_claim_cap(&caller)?;
+		currency.reserve(&caller, T::CLAIM_DEPOSIT)?;
 
-		let last_claim = self.claims.keys().last().expect("inserted; map not empty");
-		let owner = self.claims.get(last_claim).expect("owner exists");
-		let key = Self::claim_key(last_claim);
-		let encoded_owner = owner.encode();
-		if let Err(e) = kv_store().put(&key, &encoded_owner) {
-			eprintln!("Failed to persist PoE claim: {e}");
+		self.index_claim(&caller, &claim, true);
+		let info = ClaimInfo {
+			owner: caller.clone(),
+			deposit: T::CLAIM_DEPOSIT,
+			delegate: None,
+			block_number: self.now,
+			created_at: self.timestamp,
+			expires_at,
+			metadata,
+		};
+		if let Some(block) = expires_at {
+			self.expiries.insert((block, claim.clone()));
 		}
+		self.write_claim(claim.clone(), Some(info));
+		self.record_owner(claim, caller);
 		Ok(())
 	}
 
-	pub fn revoke_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		let owner = self.claims.get(&claim).ok_or("claim does not exist")?;
-		if *owner != caller {
-			return Err(&"caller is not owner");
+	/// Owner or delegate: delete the claim and its history, returning the deposit to the owner.
+	pub fn revoke_claim(
+		&mut self,
+		origin: OriginFor<T>,
+		currency: &mut T::Currency,
+		claim: T::Content,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let info = self.ensure_owner_or_delegate(&caller, &claim)?.clone();
+		self.remove_claim(currency, claim, &info);
+		Ok(())
+	}
+
+	/// Owner or delegate: move the claim's expiry to block `expires_at`, which must be later than
+	/// the current one. `None` makes the claim permanent.
+	pub fn renew_claim(
+		&mut self,
+		origin: OriginFor<T>,
+		claim: T::Content,
+		expires_at: Option<T::BlockNumber>,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut info = self.ensure_owner_or_delegate(&caller, &claim)?.clone();
+		let Some(current) = info.expires_at else {
+			return Err("claim does not expire");
+		};
+		if self.is_expired(&info) {
+			return Err("claim has expired");
+		}
+		if expires_at.is_some_and(|block| block <= current) {
+			return Err("renewal must extend the claim's expiry");
 		}
-		self.claims.remove(&claim);
 
-		let key = Self::claim_key(&claim);
-		if let Err(e) = kv_store().delete(&key) {
-			eprintln!("Failed to delete PoE claim from storage: {e}");
+		self.expiries.remove(&(current, claim.clone()));
+		if let Some(block) = expires_at {
+			self.expiries.insert((block, claim.clone()));
 		}
+		info.expires_at = expires_at;
+		self.write_claim(claim, Some(info));
+		Ok(())
+	}
+
+	/// Owner or delegate: hand the claim, and its deposit, to `to`. The delegate is cleared
+	/// either way.
+	pub fn transfer_claim(
+		&mut self,
+		origin: OriginFor<T>,
+		currency: &mut T::Currency,
+		claim: T::Content,
+		to: T::AccountId,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut info = self.ensure_owner_or_delegate(&caller, &claim)?.clone();
+		if info.owner == to {
+			return Err("claim already belongs to the target");
+		}
+		self.ensure_below_claim_cap(&to)?;
+		currency.repatriate_reserved(&info.owner, &to, info.deposit, BalanceStatus::Reserved)?;
+
+		self.index_claim(&info.owner, &claim, false);
+		self.index_claim(&to, &claim, true);
+		info.owner = to.clone();
+		info.delegate = None;
+		self.write_claim(claim.clone(), Some(info));
+		self.record_owner(claim, to);
+		Ok(())
+	}
+
+	/// Owner only: let `delegate` revoke or transfer the claim, replacing any earlier delegate.
+	/// `None` removes the delegate.
+	pub fn set_delegate(
+		&mut self,
+		origin: OriginFor<T>,
+		claim: T::Content,
+		delegate: Option<T::AccountId>,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		let mut info = self.claims.get(&claim).ok_or("claim does not exist")?.clone();
+		if info.owner != caller {
+			return Err("caller is not owner");
+		}
+		info.delegate = delegate;
+		self.write_claim(claim, Some(info));
+		Ok(())
+	}
+
+	/// Root only: delete any claim and its history, returning the deposit to the owner.
+	pub fn force_revoke_claim(
+		&mut self,
+		origin: OriginFor<T>,
+		currency: &mut T::Currency,
+		claim: T::Content,
+	) -> DispatchResult {
+		ensure_root(origin)?;
+		let info = self.claims.get(&claim).ok_or("claim does not exist")?.clone();
+		self.remove_claim(currency, claim, &info);
 		Ok(())
 	}
 }
@@ -82,6 +490,8 @@ impl<T: Config> Pallet<T> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::balances;
+	use crate::support::RuntimeOrigin;
 
 	struct TestConfig;
 	impl crate::system::Config for TestConfig {
@@ -89,14 +499,38 @@ mod tests {
 		type BlockNumber = u32;
 		type Nonce = u32;
 	}
+	impl balances::Config for TestConfig {
+		type Balance = u128;
+		const EXISTENTIAL_DEPOSIT: u128 = 10;
+	}
 	impl Config for TestConfig {
 		type Content = String;
+		type Metadata = String;
+		type Balance = u128;
+		type Currency = balances::Pallet<TestConfig>;
+		const CLAIM_DEPOSIT: u128 = 100;
+		const MAX_METADATA_LEN: usize = 32;
+		const MAX_CLAIMS_PER_ACCOUNT: u32 = 3;
+		const MAX_EXPIRIES_PER_BLOCK: u32 = 2;
 	}
 
 	fn new() -> Pallet<TestConfig> {
 		Pallet::<TestConfig>::new()
 	}
 
+	/// A fresh pallet, and balances in which alice, bob and carol hold 1_000 each.
+	fn setup() -> (Pallet<TestConfig>, balances::Pallet<TestConfig>) {
+		let mut balances = balances::Pallet::new();
+		for who in ["alice", "bob", "carol"] {
+			balances.set_balance(&who.to_string(), 1_000);
+		}
+		(new(), balances)
+	}
+
+	fn owner(poe: &Pallet<TestConfig>, claim: &str) -> Option<String> {
+		poe.get_claim(&claim.to_string()).map(|info| info.owner.clone())
+	}
+
 	#[test]
 	fn get_claim_returns_none_for_missing_content() {
 		assert_eq!(new().get_claim(&"ghost".to_string()), None);
@@ -104,77 +538,630 @@ mod tests {
 
 	#[test]
 	fn create_claim_stores_owner() {
-		let mut poe = new();
-		assert_eq!(poe.create_claim("alice".to_string(), "doc".to_string()), Ok(()));
-		assert_eq!(poe.get_claim(&"doc".to_string()), Some(&"alice".to_string()));
+		let (mut poe, mut balances) = setup();
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed("alice".to_string()),
+				&mut balances,
+				"doc".to_string(),
+				String::new(),
+				None
+			),
+			Ok(())
+		);
+		assert_eq!(owner(&poe, "doc"), Some("alice".to_string()));
+	}
+
+	#[test]
+	fn create_claim_records_block_time_and_metadata() {
+		let (mut poe, mut balances) = setup();
+		poe.on_initialize(7, 1_700_000_000_000);
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+			"v1 draft".to_string(),
+			None,
+		)
+		.unwrap();
+		let expected = ClaimInfo {
+			owner: "alice".to_string(),
+			deposit: 100,
+			delegate: None,
+			block_number: 7,
+			created_at: 1_700_000_000_000,
+			expires_at: None,
+			metadata: "v1 draft".to_string(),
+		};
+		assert_eq!(poe.get_claim(&"doc".to_string()), Some(&expected));
+		assert_eq!(new().get_claim(&"doc".to_string()), Some(&expected), "persisted");
+	}
+
+	#[test]
+	fn create_claim_rejects_oversized_metadata() {
+		let (mut poe, mut balances) = setup();
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed("alice".to_string()),
+				&mut balances,
+				"doc".to_string(),
+				"x".repeat(32),
+				None
+			),
+			Err("claim metadata is too large")
+		);
+		assert_eq!(poe.get_claim(&"doc".to_string()), None);
+	}
+
+	#[test]
+	fn claims_hold_a_deposit_until_revoked() {
+		let (mut poe, mut balances) = setup();
+		let alice = "alice".to_string();
+		poe.create_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!((balances.balance(&alice), balances.reserved_balance(&alice)), (900, 100));
+
+		poe.revoke_claim(RuntimeOrigin::Signed(alice.clone()), &mut balances, "doc".to_string())
+			.unwrap();
+		assert_eq!((balances.balance(&alice), balances.reserved_balance(&alice)), (1_000, 0));
+	}
+
+	#[test]
+	fn create_claim_needs_the_deposit() {
+		let (mut poe, mut balances) = setup();
+		let dave = "dave".to_string();
+		balances.set_balance(&dave, 50);
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed(dave.clone()),
+				&mut balances,
+				"doc".to_string(),
+				String::new(),
+				None
+			),
+			Err("Not enough funds.")
+		);
+		assert_eq!(poe.get_claim(&"doc".to_string()), None);
+		assert_eq!(poe.claim_count(&dave), 0);
+	}
+
+	#[test]
+	fn claims_per_account_are_capped() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		for doc in ["doc1", "doc2", "doc3"] {
+			poe.create_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				doc.to_string(),
+				String::new(),
+				None,
+			)
+			.unwrap();
+		}
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				"doc4".to_string(),
+				String::new(),
+				None
+			),
+			Err("account owns too many claims")
+		);
+
+		poe.create_claim(
+			RuntimeOrigin::Signed(bob.clone()),
+			&mut balances,
+			"doc4".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(
+			poe.transfer_claim(
+				RuntimeOrigin::Signed(bob),
+				&mut balances,
+				"doc4".to_string(),
+				alice.clone()
+			),
+			Err("account owns too many claims"),
+			"the cap applies to transfers too"
+		);
+		poe.revoke_claim(RuntimeOrigin::Signed(alice.clone()), &mut balances, "doc1".to_string())
+			.unwrap();
+		assert_eq!(poe.claim_count(&alice), 2);
+		assert_eq!(new().claim_count(&alice), 2, "rebuilt on load");
+	}
+
+	#[test]
+	fn owner_index_follows_create_transfer_and_revoke() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		for doc in ["doc2", "doc1", "doc3"] {
+			poe.create_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				doc.to_string(),
+				String::new(),
+				None,
+			)
+			.unwrap();
+		}
+		poe.transfer_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			"doc2".to_string(),
+			bob.clone(),
+		)
+		.unwrap();
+		poe.revoke_claim(RuntimeOrigin::Signed(alice.clone()), &mut balances, "doc3".to_string())
+			.unwrap();
+
+		assert_eq!(poe.claims_of(&alice), ["doc1"]);
+		assert_eq!(poe.claims_of(&bob), ["doc2"]);
+		assert_eq!(new().claims_of(&bob), ["doc2"], "rebuilt on load");
+		assert!(poe.claims_of(&"carol".to_string()).is_empty());
+	}
+
+	#[test]
+	fn expired_claims_are_removed_a_few_per_block() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		poe.on_initialize(1, 0);
+		for (who, doc, expiry) in [(&alice, "doc1", 5), (&alice, "doc2", 5), (&bob, "doc3", 5)] {
+			poe.create_claim(
+				RuntimeOrigin::Signed(who.clone()),
+				&mut balances,
+				doc.to_string(),
+				String::new(),
+				Some(expiry),
+			)
+			.unwrap();
+		}
+		poe.create_claim(
+			RuntimeOrigin::Signed(bob.clone()),
+			&mut balances,
+			"doc4".to_string(),
+			String::new(),
+			Some(9),
+		)
+		.unwrap();
+
+		poe.on_initialize(4, 0);
+		poe.remove_expired(&mut balances);
+		assert_eq!(poe.claim_count(&alice) + poe.claim_count(&bob), 4, "nothing due yet");
+
+		poe.on_initialize(5, 0);
+		poe.remove_expired(&mut balances);
+		assert!(poe.claims_of(&alice).is_empty(), "two removals per block");
+		assert_eq!(poe.claims_of(&bob), ["doc3", "doc4"]);
+		assert_eq!(balances.reserved_balance(&alice), 0, "deposits returned");
+
+		poe.on_initialize(6, 0);
+		poe.remove_expired(&mut balances);
+		assert_eq!(poe.claims_of(&bob), ["doc4"], "the backlog clears in the next block");
+		assert!(poe.history(&"doc3".to_string()).is_empty());
+
+		let mut reloaded = new();
+		reloaded.on_initialize(9, 0);
+		reloaded.remove_expired(&mut balances);
+		assert_eq!(reloaded.get_claim(&"doc4".to_string()), None, "expiries rebuilt on load");
+	}
+
+	#[test]
+	fn expired_claims_waiting_for_removal_are_gone() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob, doc) = ("alice".to_string(), "bob".to_string(), "doc".to_string());
+		poe.on_initialize(1, 0);
+		poe.create_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			doc.clone(),
+			String::new(),
+			Some(5),
+		)
+		.unwrap();
+		assert!(poe.prove_claim(&doc).is_some());
+
+		// Block 5 starts without `remove_expired` reaching the claim.
+		poe.on_initialize(5, 0);
+		assert_eq!(poe.get_claim(&doc), None);
+		assert_eq!(poe.prove_claim(&doc), None);
+
+		poe.create_claim(
+			RuntimeOrigin::Signed(bob.clone()),
+			&mut balances,
+			doc.clone(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(owner(&poe, "doc"), Some(bob.clone()));
+		assert_eq!(balances.reserved_balance(&alice), 0, "the old deposit is returned");
+		assert_eq!((poe.claim_count(&alice), poe.claim_count(&bob)), (0, 1));
+		assert_eq!(poe.history(&doc).len(), 1, "the new claim starts a new history");
+		poe.remove_expired(&mut balances);
+		assert_eq!(owner(&poe, "doc"), Some(bob));
+	}
+
+	#[test]
+	fn renew_claim_only_extends_expiry() {
+		let (mut poe, mut balances) = setup();
+		let (alice, doc) = ("alice".to_string(), "doc".to_string());
+		poe.on_initialize(3, 0);
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				doc.clone(),
+				String::new(),
+				Some(3)
+			),
+			Err("claim expiry is not in the future")
+		);
+		poe.create_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			doc.clone(),
+			String::new(),
+			Some(5),
+		)
+		.unwrap();
+		assert_eq!(
+			poe.renew_claim(RuntimeOrigin::Signed(alice.clone()), doc.clone(), Some(5)),
+			Err("renewal must extend the claim's expiry")
+		);
+		assert!(
+			poe.renew_claim(RuntimeOrigin::Signed("bob".to_string()), doc.clone(), Some(8))
+				.is_err()
+		);
+		poe.renew_claim(RuntimeOrigin::Signed(alice.clone()), doc.clone(), Some(8))
+			.unwrap();
+
+		poe.on_initialize(5, 0);
+		poe.remove_expired(&mut balances);
+		assert_eq!(poe.get_claim(&doc).unwrap().expires_at, Some(8), "renewed claim kept");
+
+		poe.renew_claim(RuntimeOrigin::Signed(alice.clone()), doc.clone(), None)
+			.unwrap();
+		poe.on_initialize(8, 0);
+		poe.remove_expired(&mut balances);
+		assert_eq!(owner(&poe, "doc"), Some(alice.clone()));
+		assert_eq!(
+			poe.renew_claim(RuntimeOrigin::Signed(alice), doc, Some(20)),
+			Err("claim does not expire")
+		);
+	}
+
+	#[test]
+	fn claim_proofs_check_against_the_state_root() {
+		let (mut poe, mut balances) = setup();
+		let alice = "alice".to_string();
+		assert_eq!(poe.state_root(), merkle::EMPTY_ROOT);
+		for doc in ["doc1", "doc2", "doc3"] {
+			poe.create_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				doc.to_string(),
+				String::new(),
+				None,
+			)
+			.unwrap();
+		}
+		let header = Header { block_number: 1u32, timestamp: 0, state_root: poe.state_root() };
+
+		let proof = poe.prove_claim(&"doc2".to_string()).unwrap();
+		assert_eq!(proof.info.owner, alice);
+		assert!(verify_proof(&header, &proof));
+		assert_eq!(poe.prove_claim(&"ghost".to_string()), None);
+
+		poe.revoke_claim(RuntimeOrigin::Signed(alice.clone()), &mut balances, "doc3".to_string())
+			.unwrap();
+		assert_ne!(poe.state_root(), header.state_root);
+		assert!(!verify_proof(&Header { state_root: poe.state_root(), ..header }, &proof));
+
+		// The cached leaves track every change: they match a tree built afresh from storage.
+		let (doc1, bob) = ("doc1".to_string(), "bob".to_string());
+		poe.transfer_claim(RuntimeOrigin::Signed(alice), &mut balances, doc1.clone(), bob)
+			.unwrap();
+		assert_eq!(poe.state_root(), new().state_root());
+		let header = Header { state_root: poe.state_root(), ..header };
+		assert!(verify_proof(&header, &poe.prove_claim(&doc1).unwrap()));
 	}
 
 	#[test]
 	fn create_duplicate_claim_fails() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc".to_string()).unwrap();
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
 		assert_eq!(
-			poe.create_claim("bob".to_string(), "doc".to_string()),
+			poe.create_claim(
+				RuntimeOrigin::Signed("bob".to_string()),
+				&mut balances,
+				"doc".to_string(),
+				String::new(),
+				None
+			),
 			Err("this content is already claimed")
 		);
 		// original owner unchanged
-		assert_eq!(poe.get_claim(&"doc".to_string()), Some(&"alice".to_string()));
+		assert_eq!(owner(&poe, "doc"), Some("alice".to_string()));
 	}
 
 	#[test]
 	fn revoke_claim_removes_it() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc".to_string()).unwrap();
-		assert_eq!(poe.revoke_claim("alice".to_string(), "doc".to_string()), Ok(()));
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(
+			poe.revoke_claim(
+				RuntimeOrigin::Signed("alice".to_string()),
+				&mut balances,
+				"doc".to_string()
+			),
+			Ok(())
+		);
 		assert_eq!(poe.get_claim(&"doc".to_string()), None);
 	}
 
 	#[test]
 	fn revoke_nonexistent_claim_fails() {
-		let mut poe = new();
+		let (mut poe, mut balances) = setup();
 		assert_eq!(
-			poe.revoke_claim("alice".to_string(), "ghost".to_string()),
+			poe.revoke_claim(
+				RuntimeOrigin::Signed("alice".to_string()),
+				&mut balances,
+				"ghost".to_string()
+			),
 			Err("claim does not exist")
 		);
 	}
 
+	#[test]
+	fn force_revoke_claim_is_root_only() {
+		let (mut poe, mut balances) = setup();
+		let (alice, doc) = ("alice".to_string(), "doc".to_string());
+		let signed = RuntimeOrigin::Signed(alice.clone());
+		poe.create_claim(signed.clone(), &mut balances, doc.clone(), String::new(), None)
+			.unwrap();
+		assert_eq!(
+			poe.force_revoke_claim(signed, &mut balances, doc.clone()),
+			Err("bad origin: expected root")
+		);
+
+		poe.force_revoke_claim(RuntimeOrigin::Root, &mut balances, doc.clone()).unwrap();
+		assert_eq!(poe.get_claim(&doc), None);
+		assert!(poe.claims_of(&alice).is_empty());
+		assert_eq!(balances.reserved_balance(&alice), 0, "the deposit is returned");
+	}
+
 	#[test]
 	fn revoke_claim_wrong_owner_fails() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc".to_string()).unwrap();
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
 		assert_eq!(
-			poe.revoke_claim("bob".to_string(), "doc".to_string()),
-			Err("caller is not owner")
+			poe.revoke_claim(
+				RuntimeOrigin::Signed("bob".to_string()),
+				&mut balances,
+				"doc".to_string()
+			),
+			Err("caller is not owner or delegate")
 		);
 		// claim still belongs to alice
-		assert_eq!(poe.get_claim(&"doc".to_string()), Some(&"alice".to_string()));
+		assert_eq!(owner(&poe, "doc"), Some("alice".to_string()));
 	}
 
 	#[test]
 	fn reclaim_after_revoke_succeeds() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc".to_string()).unwrap();
-		poe.revoke_claim("alice".to_string(), "doc".to_string()).unwrap();
-		assert_eq!(poe.create_claim("bob".to_string(), "doc".to_string()), Ok(()));
-		assert_eq!(poe.get_claim(&"doc".to_string()), Some(&"bob".to_string()));
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		poe.revoke_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc".to_string(),
+		)
+		.unwrap();
+		assert_eq!(
+			poe.create_claim(
+				RuntimeOrigin::Signed("bob".to_string()),
+				&mut balances,
+				"doc".to_string(),
+				String::new(),
+				None
+			),
+			Ok(())
+		);
+		assert_eq!(owner(&poe, "doc"), Some("bob".to_string()));
+	}
+
+	#[test]
+	fn transfer_claim_moves_ownership_and_records_history() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+		poe.on_initialize(1, 1_000);
+		poe.create_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		poe.on_initialize(5, 5_000);
+		poe.transfer_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			"doc".to_string(),
+			bob.clone(),
+		)
+		.unwrap();
+		assert_eq!(
+			poe.transfer_claim(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				"doc".to_string(),
+				carol.clone()
+			),
+			Err("caller is not owner or delegate")
+		);
+		assert_eq!(
+			poe.transfer_claim(
+				RuntimeOrigin::Signed(bob.clone()),
+				&mut balances,
+				"doc".to_string(),
+				bob.clone()
+			),
+			Err("claim already belongs to the target")
+		);
+
+		let info = poe.get_claim(&"doc".to_string()).unwrap();
+		assert_eq!((&info.owner, info.block_number), (&bob, 1), "creation block is kept");
+		assert_eq!(balances.reserved_balance(&alice), 0);
+		assert_eq!(balances.reserved_balance(&bob), 100, "the deposit moves with the claim");
+		let history = [
+			OwnershipRecord { owner: alice, block_number: 1, timestamp: 1_000 },
+			OwnershipRecord { owner: bob, block_number: 5, timestamp: 5_000 },
+		];
+		assert_eq!(poe.history(&"doc".to_string()), history);
+		assert_eq!(new().history(&"doc".to_string()), history, "persisted");
+	}
+
+	#[test]
+	fn delegate_may_transfer_or_revoke_until_the_claim_moves() {
+		let (mut poe, mut balances) = setup();
+		let (alice, bob, carol) = ("alice".to_string(), "bob".to_string(), "carol".to_string());
+		poe.create_claim(
+			RuntimeOrigin::Signed(alice.clone()),
+			&mut balances,
+			"doc".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(
+			poe.set_delegate(
+				RuntimeOrigin::Signed(bob.clone()),
+				"doc".to_string(),
+				Some(bob.clone())
+			),
+			Err("caller is not owner")
+		);
+		poe.set_delegate(
+			RuntimeOrigin::Signed(alice.clone()),
+			"doc".to_string(),
+			Some(bob.clone()),
+		)
+		.unwrap();
+
+		poe.transfer_claim(
+			RuntimeOrigin::Signed(bob.clone()),
+			&mut balances,
+			"doc".to_string(),
+			carol.clone(),
+		)
+		.unwrap();
+		assert_eq!(owner(&poe, "doc"), Some(carol.clone()));
+		assert_eq!(poe.get_claim(&"doc".to_string()).unwrap().delegate, None, "cleared");
+		assert!(
+			poe.revoke_claim(RuntimeOrigin::Signed(bob.clone()), &mut balances, "doc".to_string())
+				.is_err()
+		);
+
+		poe.set_delegate(
+			RuntimeOrigin::Signed(carol.clone()),
+			"doc".to_string(),
+			Some(bob.clone()),
+		)
+		.unwrap();
+		poe.revoke_claim(RuntimeOrigin::Signed(bob), &mut balances, "doc".to_string())
+			.unwrap();
+		assert_eq!(poe.get_claim(&"doc".to_string()), None);
+		assert_eq!(balances.balance(&carol), 1_100, "the deposit goes back to the owner");
+		assert!(poe.history(&"doc".to_string()).is_empty(), "history goes with the claim");
 	}
 
 	#[test]
 	fn multiple_claims_are_independent() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc1".to_string()).unwrap();
-		poe.create_claim("bob".to_string(), "doc2".to_string()).unwrap();
-		assert_eq!(poe.get_claim(&"doc1".to_string()), Some(&"alice".to_string()));
-		assert_eq!(poe.get_claim(&"doc2".to_string()), Some(&"bob".to_string()));
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc1".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		poe.create_claim(
+			RuntimeOrigin::Signed("bob".to_string()),
+			&mut balances,
+			"doc2".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(owner(&poe, "doc1"), Some("alice".to_string()));
+		assert_eq!(owner(&poe, "doc2"), Some("bob".to_string()));
 	}
 
 	#[test]
 	fn revoking_one_claim_does_not_affect_others() {
-		let mut poe = new();
-		poe.create_claim("alice".to_string(), "doc1".to_string()).unwrap();
-		poe.create_claim("alice".to_string(), "doc2".to_string()).unwrap();
-		poe.revoke_claim("alice".to_string(), "doc1".to_string()).unwrap();
+		let (mut poe, mut balances) = setup();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc1".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		poe.create_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc2".to_string(),
+			String::new(),
+			None,
+		)
+		.unwrap();
+		poe.revoke_claim(
+			RuntimeOrigin::Signed("alice".to_string()),
+			&mut balances,
+			"doc1".to_string(),
+		)
+		.unwrap();
 		assert_eq!(poe.get_claim(&"doc1".to_string()), None);
-		assert_eq!(poe.get_claim(&"doc2".to_string()), Some(&"alice".to_string()));
+		assert_eq!(owner(&poe, "doc2"), Some("alice".to_string()));
 	}
 }
@@ -0,0 +1,247 @@
+use crate::support::{CryptoScheme, MultiSignature, MultiSigner, Pair, Signer};
+use parity_scale_codec::{Decode, Encode};
+use std::path::{Path, PathBuf};
+
+/// Key loaded from a plain key file holding `<scheme>:0x<hex seed>`, e.g. `sr25519:0x1f…`.
+pub struct FileSigner {
+	pair: Pair,
+}
+
+impl FileSigner {
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+		let path = path.as_ref();
+		let contents = std::fs::read_to_string(path)
+			.map_err(|e| format!("failed to read key file '{}': {e}", path.display()))?;
+		let (scheme, seed) = contents
+			.trim()
+			.split_once(':')
+			.ok_or("key file must contain `<scheme>:0x<hex seed>`")?;
+		let scheme: CryptoScheme = scheme.parse()?;
+		let bytes = hex::decode(seed.trim_start_matches("0x"))
+			.map_err(|e| format!("invalid seed hex: {e}"))?;
+		let seed: [u8; 32] = bytes.try_into().map_err(|_| "seed must be 32 bytes")?;
+		Ok(Self { pair: Pair::try_from_seed(scheme, &seed)? })
+	}
+
+	/// Write `seed` as a key file of the given scheme.
+	pub fn save(
+		path: impl AsRef<Path>,
+		scheme: CryptoScheme,
+		seed: &[u8; 32],
+	) -> Result<(), String> {
+		std::fs::write(path, format!("{scheme}:0x{}\n", hex::encode(seed)))
+			.map_err(|e| format!("failed to write key file: {e}"))
+	}
+}
+
+impl Signer for FileSigner {
+	fn public(&self) -> Result<MultiSigner, String> {
+		Ok(self.pair.public())
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, String> {
+		Ok(self.pair.sign(payload))
+	}
+}
+
+/// Requests understood by the signer daemon. Each frame is a little-endian `u32` length
+/// followed by the SCALE-encoded message.
+#[derive(Debug, Encode, Decode)]
+pub enum SignerRequest {
+	Public,
+	Sign(Vec<u8>),
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum SignerResponse {
+	Public(MultiSigner),
+	Signature(MultiSignature),
+	Error(String),
+}
+
+/// Largest frame either side accepts, checked before allocating for it.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+fn write_frame(w: &mut impl std::io::Write, msg: &impl Encode) -> std::io::Result<()> {
+	let bytes = msg.encode();
+	w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+	w.write_all(&bytes)?;
+	w.flush()
+}
+
+fn read_frame<T: Decode>(r: &mut impl std::io::Read) -> std::io::Result<T> {
+	let mut len = [0u8; 4];
+	r.read_exact(&mut len)?;
+	let len = u32::from_le_bytes(len) as usize;
+	if len > MAX_FRAME_LEN {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!("frame of {len} bytes exceeds the {MAX_FRAME_LEN} byte limit"),
+		));
+	}
+	let mut bytes = vec![0u8; len];
+	r.read_exact(&mut bytes)?;
+	T::decode(&mut &bytes[..])
+		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Client side of the signer daemon (`serve`), which holds the key and only ever returns public
+/// keys and signatures. Opens one connection per request, so a daemon restart is harmless.
+#[cfg(unix)]
+pub struct SocketSigner {
+	path: PathBuf,
+}
+
+#[cfg(unix)]
+impl SocketSigner {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+
+	fn request(&self, req: &SignerRequest) -> Result<SignerResponse, String> {
+		let mut stream = std::os::unix::net::UnixStream::connect(&self.path).map_err(|e| {
+			format!("failed to connect to signer at '{}': {e}", self.path.display())
+		})?;
+		write_frame(&mut stream, req).map_err(|e| format!("signer request failed: {e}"))?;
+		read_frame(&mut stream).map_err(|e| format!("signer response failed: {e}"))
+	}
+}
+
+#[cfg(unix)]
+impl Signer for SocketSigner {
+	fn public(&self) -> Result<MultiSigner, String> {
+		match self.request(&SignerRequest::Public)? {
+			SignerResponse::Public(pk) => Ok(pk),
+			SignerResponse::Error(e) => Err(e),
+			other => Err(format!("unexpected signer response: {other:?}")),
+		}
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, String> {
+		match self.request(&SignerRequest::Sign(payload.to_vec()))? {
+			SignerResponse::Signature(sig) => Ok(sig),
+			SignerResponse::Error(e) => Err(e),
+			other => Err(format!("unexpected signer response: {other:?}")),
+		}
+	}
+}
+
+/// Answer requests on one connection until the client hangs up.
+#[cfg(unix)]
+pub fn handle_connection(
+	mut stream: std::os::unix::net::UnixStream,
+	signer: &dyn Signer,
+) -> std::io::Result<()> {
+	loop {
+		let req: SignerRequest = match read_frame(&mut stream) {
+			Ok(req) => req,
+			Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+			Err(e) => return Err(e),
+		};
+		let res = match req {
+			SignerRequest::Public => signer.public().map(SignerResponse::Public),
+			SignerRequest::Sign(payload) => signer.sign(&payload).map(SignerResponse::Signature),
+		};
+		write_frame(&mut stream, &res.unwrap_or_else(SignerResponse::Error))?;
+	}
+}
+
+/// Run the signer daemon on `socket`, serving connections one at a time. Replaces a stale
+/// socket left behind by a previous run, but refuses to touch anything else at that path.
+#[cfg(unix)]
+pub fn serve(socket: impl AsRef<Path>, signer: &dyn Signer) -> std::io::Result<()> {
+	use std::os::unix::fs::FileTypeExt;
+	let socket = socket.as_ref();
+	match std::fs::symlink_metadata(socket) {
+		Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(socket)?,
+		Ok(_) => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::AlreadyExists,
+				format!("'{}' exists and is not a socket", socket.display()),
+			));
+		},
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+		Err(e) => return Err(e),
+	}
+	let listener = std::os::unix::net::UnixListener::bind(socket)?;
+	println!("[signer] listening on {}", socket.display());
+	for stream in listener.incoming() {
+		if let Err(e) = handle_connection(stream?, signer) {
+			eprintln!("[signer] connection error: {e}");
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::support::{UncheckedExtrinsic, keyring::AccountKeyring::Alice};
+
+	#[test]
+	fn file_signer_roundtrip() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("key");
+		FileSigner::save(&path, CryptoScheme::Sr25519, &[9u8; 32]).unwrap();
+
+		let signer = FileSigner::load(&path).unwrap();
+		let expected = Pair::from_seed(CryptoScheme::Sr25519, &[9u8; 32]).public();
+		assert_eq!(Signer::public(&signer).unwrap(), expected);
+
+		let ext = UncheckedExtrinsic::sign_with(&signer, 0, u64::MAX, 42u32).unwrap();
+		assert!(ext.verify().is_ok());
+	}
+
+	#[test]
+	fn file_signer_rejects_malformed_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("key");
+		std::fs::write(&path, "ed25519:0x1234").unwrap();
+		assert!(FileSigner::load(&path).is_err());
+	}
+
+	#[test]
+	fn oversized_frames_are_rejected() {
+		let mut frame = ((MAX_FRAME_LEN + 1) as u32).to_le_bytes().to_vec();
+		frame.extend([0u8; 16]);
+		let err = read_frame::<SignerRequest>(&mut &frame[..]).unwrap_err();
+		assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+		let mut frame = Vec::new();
+		write_frame(&mut frame, &SignerRequest::Sign(vec![1; 32])).unwrap();
+		assert!(matches!(read_frame(&mut &frame[..]), Ok(SignerRequest::Sign(p)) if p == [1; 32]));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn serve_refuses_to_replace_a_regular_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("signer.sock");
+		std::fs::write(&path, "not a socket").unwrap();
+		let err = serve(&path, &Alice.signing_key()).unwrap_err();
+		assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn socket_signer_signs_via_daemon() {
+		let dir = tempfile::tempdir().unwrap();
+		let socket = dir.path().join("signer.sock");
+		let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+		let daemon = std::thread::spawn(move || {
+			let pair = Alice.signing_key();
+			// One connection per request: `public` then `sign`.
+			for _ in 0..2 {
+				let (stream, _) = listener.accept().unwrap();
+				handle_connection(stream, &pair).unwrap();
+			}
+		});
+
+		let signer = SocketSigner::new(&socket);
+		let ext = UncheckedExtrinsic::sign_with(&signer, 3, u64::MAX, 7u32).unwrap();
+		assert_eq!(ext.signer, Alice.public());
+		assert!(ext.verify().is_ok());
+		daemon.join().unwrap();
+	}
+}
@@ -0,0 +1,246 @@
+use core::fmt::Debug;
+use num::traits::{CheckedSub, Zero};
+use parity_scale_codec::{Decode, Encode};
+use std::collections::BTreeMap;
+
+use crate::{
+	support::{
+		DispatchResult, DispatchesAsRoot, KeyValueStore, ensure_signed, kv_store, load_prefix,
+		write_prefix,
+	},
+	system::{self, OriginFor},
+};
+
+const KEY_SUDO: &[u8] = b"sudo:key";
+// Outside `sudo:`, like the other pallets' secondary prefixes.
+const PREFIX_OUTCOME: &[u8] = b"sudo_outcome:";
+
+pub trait Config: system::Config {
+	/// The calls `sudo` dispatches as root; the runtime's `RuntimeCall`.
+	type RuntimeCall: Debug + Encode + Decode;
+	/// Number of most recent blocks whose outcomes are kept; older ones are deleted.
+	const OUTCOME_HISTORY: Self::BlockNumber;
+}
+
+/// How one `sudo` call's inner call went, stored under `sudo_outcome:<block, index>`, where
+/// `index` counts the block's sudo calls from 0.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct SudoOutcome<AccountId> {
+	/// The sudo key that made the call.
+	pub key: AccountId,
+	pub result: Result<(), String>,
+}
+
+type Outcomes<T> = BTreeMap<
+	(<T as system::Config>::BlockNumber, u32),
+	SudoOutcome<<T as system::Config>::AccountId>,
+>;
+
+#[derive(Debug)]
+pub struct Pallet<T: Config> {
+	key: Option<T::AccountId>,
+	/// The call `sudo` accepted, with the key that made it. The runtime takes it within the same
+	/// dispatch, right after the `sudo` call returns.
+	pending: Option<(T::AccountId, T::RuntimeCall)>,
+	outcomes: Outcomes<T>,
+	/// Current block, set by `on_initialize`.
+	now: T::BlockNumber,
+}
+
+/// `RuntimeCall` is not `Clone`, and nothing is pending between dispatches, which is when the
+/// runtime snapshots its pallets.
+impl<T: Config> Clone for Pallet<T> {
+	fn clone(&self) -> Self {
+		debug_assert!(self.pending.is_none(), "cloned with a sudo call pending");
+		Self {
+			key: self.key.clone(),
+			pending: None,
+			outcomes: self.outcomes.clone(),
+			now: self.now,
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn new() -> Self {
+		let key = kv_store().get(KEY_SUDO).and_then(|bytes| Decode::decode(&mut &bytes[..]).ok());
+		Self {
+			key,
+			pending: None,
+			outcomes: load_prefix(PREFIX_OUTCOME),
+			now: T::BlockNumber::zero(),
+		}
+	}
+
+	/// Called by the runtime at the start of every block. Deletes the outcomes that have fallen
+	/// out of `Config::OUTCOME_HISTORY`.
+	pub fn on_initialize(&mut self, now: T::BlockNumber) {
+		self.now = now;
+		let Some(cutoff) = now.checked_sub(&T::OUTCOME_HISTORY) else { return };
+		let stale: Vec<_> = self.outcomes.range(..=(cutoff, u32::MAX)).map(|(k, _)| *k).collect();
+		for key in stale {
+			write_prefix(&mut self.outcomes, PREFIX_OUTCOME, key, None);
+		}
+	}
+
+	/// The account allowed to make sudo calls, if any.
+	pub fn key(&self) -> Option<&T::AccountId> {
+		self.key.as_ref()
+	}
+
+	/// Install the sudo key, as genesis does.
+	pub fn initialize_key(&mut self, key: T::AccountId) {
+		if let Err(e) = kv_store().put(KEY_SUDO, &key.encode()) {
+			eprintln!("Failed to persist sudo key: {e}");
+		}
+		self.key = Some(key);
+	}
+
+	/// Record `result` as the outcome of `key`'s latest sudo call.
+	pub fn record_outcome(&mut self, key: T::AccountId, result: DispatchResult) {
+		let index = self.outcomes.range((self.now, 0)..).count() as u32;
+		let outcome = SudoOutcome { key, result: result.map_err(str::to_string) };
+		write_prefix(&mut self.outcomes, PREFIX_OUTCOME, (self.now, index), Some(outcome));
+	}
+
+	/// The outcomes of the sudo calls made in block `block_number`, in order.
+	pub fn outcomes(&self, block_number: T::BlockNumber) -> Vec<&SudoOutcome<T::AccountId>> {
+		self.outcomes
+			.range((block_number, 0)..=(block_number, u32::MAX))
+			.map(|(_, o)| o)
+			.collect()
+	}
+
+	fn ensure_key(&self, caller: &T::AccountId) -> DispatchResult {
+		if self.key.as_ref() != Some(caller) {
+			return Err("caller is not the sudo key");
+		}
+		Ok(())
+	}
+}
+
+/// The runtime field is marked `#[dispatches_as_root]`, so a `sudo` call's inner call is
+/// dispatched and its outcome recorded before the `sudo` call's own dispatch returns.
+impl<T: Config> DispatchesAsRoot<T::RuntimeCall> for Pallet<T> {
+	type Caller = T::AccountId;
+
+	fn take_root_call(&mut self) -> Option<(T::AccountId, T::RuntimeCall)> {
+		self.pending.take()
+	}
+
+	fn root_call_dispatched(&mut self, key: T::AccountId, result: DispatchResult) {
+		self.record_outcome(key, result);
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Key only: dispatch `call` as root. The sudo call itself succeeds once the key checks out;
+	/// the runtime then dispatches `call` at once and how that went is recorded as a
+	/// `SudoOutcome`.
+	// The box keeps `Call`, which `RuntimeCall` contains, from containing itself.
+	#[allow(clippy::boxed_local)]
+	pub fn sudo(&mut self, origin: OriginFor<T>, call: Box<T::RuntimeCall>) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		self.ensure_key(&caller)?;
+		self.pending = Some((caller, *call));
+		Ok(())
+	}
+
+	/// Key only: hand the sudo key to `new`.
+	pub fn set_key(&mut self, origin: OriginFor<T>, new: T::AccountId) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		self.ensure_key(&caller)?;
+		self.initialize_key(new);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::support::RuntimeOrigin;
+
+	struct TestConfig;
+
+	impl system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+	}
+
+	impl Config for TestConfig {
+		type RuntimeCall = String;
+		const OUTCOME_HISTORY: u32 = 2;
+	}
+
+	fn new() -> Pallet<TestConfig> {
+		let mut sudo = Pallet::new();
+		sudo.initialize_key("alice".to_string());
+		sudo
+	}
+
+	#[test]
+	fn only_the_key_can_sudo() {
+		let mut sudo = new();
+		let call = Box::new("call".to_string());
+		let err = Err("caller is not the sudo key");
+		assert_eq!(sudo.sudo(RuntimeOrigin::Signed("bob".to_string()), call.clone()), err);
+		assert_eq!(
+			sudo.sudo(RuntimeOrigin::Root, call.clone()),
+			Err("bad origin: expected a signed origin")
+		);
+		assert_eq!(sudo.take_root_call(), None);
+
+		sudo.sudo(RuntimeOrigin::Signed("alice".to_string()), call).unwrap();
+		assert_eq!(sudo.take_root_call(), Some(("alice".to_string(), "call".to_string())));
+		assert_eq!(sudo.take_root_call(), None);
+	}
+
+	#[test]
+	fn set_key_hands_over_sudo() {
+		let mut sudo = new();
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let err = Err("caller is not the sudo key");
+		assert_eq!(sudo.set_key(RuntimeOrigin::Signed(bob.clone()), bob.clone()), err);
+
+		sudo.set_key(RuntimeOrigin::Signed(alice.clone()), bob.clone()).unwrap();
+		assert_eq!(sudo.key(), Some(&bob));
+		assert_eq!(sudo.set_key(RuntimeOrigin::Signed(alice.clone()), alice), err);
+		assert_eq!(Pallet::<TestConfig>::new().key(), Some(&bob), "the key is persisted");
+	}
+
+	#[test]
+	fn outcomes_are_recorded_per_block() {
+		let mut sudo = new();
+		let alice = "alice".to_string();
+		sudo.on_initialize(1);
+		sudo.record_outcome(alice.clone(), Ok(()));
+		sudo.record_outcome(alice.clone(), Err("failed"));
+		sudo.on_initialize(2);
+		sudo.record_outcome(alice.clone(), Ok(()));
+
+		let failed = SudoOutcome { key: alice.clone(), result: Err("failed".to_string()) };
+		let ok = SudoOutcome { key: alice, result: Ok(()) };
+		assert_eq!(sudo.outcomes(1), vec![&ok, &failed]);
+		assert_eq!(Pallet::<TestConfig>::new().outcomes(2), vec![&ok]);
+		assert!(sudo.outcomes(3).is_empty());
+	}
+
+	#[test]
+	fn only_recent_outcomes_are_kept() {
+		let mut sudo = new();
+		let alice = "alice".to_string();
+		for block in 10..13 {
+			sudo.on_initialize(block);
+			sudo.record_outcome(alice.clone(), Ok(()));
+		}
+		assert!(sudo.outcomes(10).is_empty(), "block 12 keeps only blocks 11 and 12");
+		assert_eq!((sudo.outcomes(11).len(), sudo.outcomes(12).len()), (1, 1));
+
+		sudo.on_initialize(13);
+		assert!(sudo.outcomes(11).is_empty());
+		assert_eq!(sudo.outcomes(12).len(), 1);
+		assert!(Pallet::<TestConfig>::new().outcomes(11).is_empty(), "deleted from storage");
+	}
+}
@@ -1,8 +1,8 @@
 use core::fmt;
 use once_cell::sync::Lazy;
 use parity_scale_codec::{Decode, Encode};
-use rocksdb::{DB, IteratorMode, Options};
-use std::sync::OnceLock;
+use rocksdb::{DB, IteratorMode, Options, WriteBatch};
+use std::sync::{Mutex, OnceLock};
 
 /// Override the RocksDB path before any storage operation is performed.
 /// Defaults to `"state.db"` in the current working directory.
@@ -34,6 +34,90 @@ impl From<[u8; 32]> for AccountId32 {
 	}
 }
 
+/// SS58 network prefix used when formatting addresses. 42 is Substrate's generic prefix.
+pub const SS58_PREFIX: u16 = 42;
+
+const SS58_CHECKSUM_PREIMAGE: &[u8] = b"SS58PRE";
+
+fn ss58_checksum(data: &[u8]) -> [u8; 2] {
+	use blake2::{Blake2b512, Digest};
+	let hash = Blake2b512::new()
+		.chain_update(SS58_CHECKSUM_PREIMAGE)
+		.chain_update(data)
+		.finalize();
+	[hash[0], hash[1]]
+}
+
+impl AccountId32 {
+	/// SS58 address: base58 of `prefix ‖ account ‖ checksum`, where the checksum is the first two
+	/// bytes of `blake2b_512("SS58PRE" ‖ prefix ‖ account)`. Prefixes below 64 take one byte.
+	pub fn to_ss58check_with_prefix(&self, prefix: u16) -> String {
+		let mut data = match prefix {
+			0..=63 => vec![prefix as u8],
+			64..=16_383 => {
+				let first = ((prefix & 0b1111_1100) as u8) >> 2;
+				let second = ((prefix >> 8) as u8) | (((prefix & 0b11) as u8) << 6);
+				vec![first | 0b0100_0000, second]
+			},
+			_ => panic!("SS58 prefix must be below 16384"),
+		};
+		data.extend(self.0);
+		let checksum = ss58_checksum(&data);
+		data.extend(checksum);
+		bs58::encode(data).into_string()
+	}
+
+	/// Parse an SS58 address, returning the account and its network prefix.
+	pub fn from_ss58check_with_prefix(s: &str) -> Result<(Self, u16), String> {
+		let data = bs58::decode(s).into_vec().map_err(|e| format!("invalid base58: {e}"))?;
+		let (prefix, prefix_len) = match data.first() {
+			Some(&b @ 0..=63) => (b as u16, 1),
+			Some(&b @ 64..=127) if data.len() > 1 => {
+				let lower = (b << 2) | (data[1] >> 6);
+				let upper = data[1] & 0b0011_1111;
+				((lower as u16) | ((upper as u16) << 8), 2)
+			},
+			_ => return Err("invalid SS58 prefix".into()),
+		};
+		if data.len() != prefix_len + 32 + 2 {
+			return Err("SS58 address must encode a 32-byte account".into());
+		}
+		let (body, checksum) = data.split_at(prefix_len + 32);
+		if ss58_checksum(body) != checksum {
+			return Err("invalid SS58 checksum".into());
+		}
+		let mut account = [0u8; 32];
+		account.copy_from_slice(&body[prefix_len..]);
+		Ok((Self(account), prefix))
+	}
+}
+
+/// Formats as an SS58 address with `SS58_PREFIX`.
+impl fmt::Display for AccountId32 {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.to_ss58check_with_prefix(SS58_PREFIX))
+	}
+}
+
+/// Accepts an SS58 address with `SS58_PREFIX`, or `0x`-prefixed / bare 64-char hex.
+impl core::str::FromStr for AccountId32 {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let hex = s.strip_prefix("0x").unwrap_or(s);
+		if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+			let mut account = [0u8; 32];
+			hex::decode_to_slice(hex, &mut account).map_err(|e| format!("invalid hex: {e}"))?;
+			return Ok(Self(account));
+		}
+		let (account, prefix) = Self::from_ss58check_with_prefix(s)?;
+		if prefix != SS58_PREFIX {
+			return Err(format!("address is for network prefix {prefix}, expected {SS58_PREFIX}"));
+		}
+		Ok(account)
+	}
+}
+
 impl fmt::Debug for AccountId32 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let hex: String = self.0.iter().map(|b| format!("{b:02x}")).collect();
@@ -50,53 +134,444 @@ pub struct Block<Header, Extrinsic> {
 #[derive(Clone, Encode, Decode)]
 pub struct Header<BlockNumber> {
 	pub block_number: BlockNumber,
+	/// Milliseconds since the Unix epoch by the author's clock. Never earlier than the parent's,
+	/// nor more than `MAX_TIMESTAMP_DRIFT_MILLIS` ahead of the importing node's clock.
+	pub timestamp: u64,
+	/// `RuntimeHooks::state_root` after the block is applied. Filled in by the author.
+	pub state_root: [u8; 32],
+}
+
+/// The local clock in milliseconds since the Unix epoch, for block authors filling in
+/// `Header::timestamp`.
+pub fn now_millis() -> u64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.expect("clock is after the Unix epoch")
+		.as_millis() as u64
+}
+
+/// How far ahead of the local clock a block's timestamp may be, in milliseconds, to allow for
+/// clock skew between nodes. A block further ahead is rejected.
+pub const MAX_TIMESTAMP_DRIFT_MILLIS: u64 = 30_000;
+
+/// Signature schemes an account key can use. Mirrors `sp_core::crypto` key types.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CryptoScheme {
+	#[default]
+	Ed25519,
+	Sr25519,
+	Ecdsa,
 }
 
+impl core::str::FromStr for CryptoScheme {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"ed25519" => Ok(Self::Ed25519),
+			"sr25519" => Ok(Self::Sr25519),
+			"ecdsa" | "secp256k1" => Ok(Self::Ecdsa),
+			_ => Err(format!("unknown signature scheme '{s}'; use ed25519 / sr25519 / ecdsa")),
+		}
+	}
+}
+
+impl fmt::Display for CryptoScheme {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.pad(match self {
+			Self::Ed25519 => "ed25519",
+			Self::Sr25519 => "sr25519",
+			Self::Ecdsa => "ecdsa",
+		})
+	}
+}
+
+/// Signing context used for sr25519, matching Substrate so signatures are interchangeable.
+const SR25519_CONTEXT: &[u8] = b"substrate";
+
+/// 256-bit Blake2b digest, the hash used for ecdsa account ids and signed-payload prehashing.
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+	use blake2::{Blake2b, Digest, digest::consts::U32};
+	Blake2b::<U32>::digest(data).into()
+}
+
+/// `blake2_256` of everything `reader` yields, read in chunks so large files need not fit in
+/// memory. Equal to `blake2_256` of the same bytes.
+pub fn blake2_256_reader(mut reader: impl std::io::Read) -> std::io::Result<[u8; 32]> {
+	use blake2::{Blake2b, Digest, digest::consts::U32};
+	let mut hasher = Blake2b::<U32>::new();
+	std::io::copy(&mut reader, &mut hasher)?;
+	Ok(hasher.finalize().into())
+}
+
+/// Parse a 32-byte hash written as hex, with or without a `0x` prefix.
+pub fn parse_hash(s: &str) -> Result<[u8; 32], String> {
+	let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|e| format!("invalid hex: {e}"))?;
+	bytes.try_into().map_err(|_| "hash must be 32 bytes".to_string())
+}
+
+/// Public key of any supported scheme. Mirrors `sp_runtime::MultiSigner`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum MultiSigner {
+	Ed25519([u8; 32]),
+	Sr25519([u8; 32]),
+	/// Compressed secp256k1 public key.
+	Ecdsa([u8; 33]),
+}
+
+impl MultiSigner {
+	/// Ed25519 and sr25519 keys are used as the account id directly; ecdsa keys are 33 bytes,
+	/// so the account id is `blake2_256(compressed_pubkey)` as in Substrate.
+	pub fn into_account(self) -> AccountId32 {
+		match self {
+			Self::Ed25519(pk) | Self::Sr25519(pk) => AccountId32(pk),
+			Self::Ecdsa(pk) => AccountId32(blake2_256(&pk)),
+		}
+	}
+
+	pub fn scheme(&self) -> CryptoScheme {
+		match self {
+			Self::Ed25519(_) => CryptoScheme::Ed25519,
+			Self::Sr25519(_) => CryptoScheme::Sr25519,
+			Self::Ecdsa(_) => CryptoScheme::Ecdsa,
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		match self {
+			Self::Ed25519(pk) | Self::Sr25519(pk) => pk,
+			Self::Ecdsa(pk) => pk,
+		}
+	}
+}
+
+/// Decode an ed25519 signature and its key, rejecting what the single and batch checks could
+/// disagree on: a key or `R` of small order or with a torsion component, and a non-canonical
+/// `R`. What is left verifies the same with `verify_strict` as in `ed25519_dalek::verify_batch`,
+/// whatever else is in the batch.
+fn ed25519_parts(
+	sig: &[u8; 64],
+	signer: &AccountId32,
+) -> Result<(ed25519_dalek::Signature, ed25519_dalek::VerifyingKey), &'static str> {
+	use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+	let prime_order = |p: &EdwardsPoint| !p.is_small_order() && p.is_torsion_free();
+
+	let vk = ed25519_dalek::VerifyingKey::from_bytes(signer.as_bytes())
+		.map_err(|_| "invalid public key")?;
+	if !prime_order(&vk.to_edwards()) {
+		return Err("invalid public key");
+	}
+	let r_bytes: [u8; 32] = sig[..32].try_into().expect("a signature starts with R");
+	let r = CompressedEdwardsY(r_bytes).decompress().ok_or("invalid signature")?;
+	if r.compress().to_bytes() != r_bytes || !prime_order(&r) {
+		return Err("invalid signature");
+	}
+	Ok((ed25519_dalek::Signature::from_bytes(sig), vk))
+}
+
+/// Signature of any supported scheme. Mirrors `sp_runtime::MultiSignature`.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub enum MultiSignature {
+	Ed25519([u8; 64]),
+	Sr25519([u8; 64]),
+	/// Recoverable secp256k1 signature `r ‖ s ‖ v` over `blake2_256(payload)`.
+	Ecdsa([u8; 65]),
+}
+
+impl MultiSignature {
+	/// Verify `msg` against `signer`. For ecdsa the public key is recovered from the signature
+	/// and must hash to `signer`, so an account id is all the verifier needs for every scheme.
+	pub fn verify(&self, msg: &[u8], signer: &AccountId32) -> DispatchResult {
+		match self {
+			Self::Ed25519(sig) => {
+				let (sig, vk) = ed25519_parts(sig, signer)?;
+				vk.verify_strict(msg, &sig).map_err(|_| "invalid signature")
+			},
+			Self::Sr25519(sig) => {
+				let pk = schnorrkel::PublicKey::from_bytes(signer.as_bytes())
+					.map_err(|_| "invalid public key")?;
+				let sig =
+					schnorrkel::Signature::from_bytes(sig).map_err(|_| "invalid signature")?;
+				pk.verify_simple(SR25519_CONTEXT, msg, &sig).map_err(|_| "invalid signature")
+			},
+			Self::Ecdsa(sig) => {
+				use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+				let rs = Signature::from_slice(&sig[..64]).map_err(|_| "invalid signature")?;
+				let v = RecoveryId::from_byte(sig[64]).ok_or("invalid signature")?;
+				let vk = VerifyingKey::recover_from_prehash(&blake2_256(msg), &rs, v)
+					.map_err(|_| "invalid signature")?;
+				let mut pk = [0u8; 33];
+				pk.copy_from_slice(vk.to_encoded_point(true).as_bytes());
+				if MultiSigner::Ecdsa(pk).into_account() != *signer {
+					return Err("invalid signature");
+				}
+				Ok(())
+			},
+		}
+	}
+
+	pub fn scheme(&self) -> CryptoScheme {
+		match self {
+			Self::Ed25519(_) => CryptoScheme::Ed25519,
+			Self::Sr25519(_) => CryptoScheme::Sr25519,
+			Self::Ecdsa(_) => CryptoScheme::Ecdsa,
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		match self {
+			Self::Ed25519(sig) | Self::Sr25519(sig) => sig,
+			Self::Ecdsa(sig) => sig,
+		}
+	}
+}
+
+impl fmt::Debug for MultiSignature {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let hex: String = self.as_bytes().iter().map(|b| format!("{b:02x}")).collect();
+		write!(f, "{:?}(0x{}…{})", self.scheme(), &hex[..8], &hex[hex.len() - 8..])
+	}
+}
+
+/// A secret key of any supported scheme. Mirrors `sp_core::Pair` across key types.
+#[derive(Clone)]
+pub enum Pair {
+	Ed25519(ed25519_dalek::SigningKey),
+	Sr25519(schnorrkel::Keypair),
+	Ecdsa(k256::ecdsa::SigningKey),
+}
+
+impl Pair {
+	/// Derive a key of the given scheme from a 32-byte seed.
+	pub fn from_seed(scheme: CryptoScheme, seed: &[u8; 32]) -> Self {
+		Self::try_from_seed(scheme, seed).expect("seed must be a valid secret key")
+	}
+
+	/// Like `from_seed`, but rejects a seed that is not a valid secp256k1 scalar instead of
+	/// panicking. Use for seeds that come from outside the process.
+	pub fn try_from_seed(scheme: CryptoScheme, seed: &[u8; 32]) -> Result<Self, String> {
+		Ok(match scheme {
+			CryptoScheme::Ed25519 => Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(seed)),
+			CryptoScheme::Sr25519 => Self::Sr25519(
+				schnorrkel::MiniSecretKey::from_bytes(seed)
+					.expect("32-byte seed is a valid mini secret key")
+					.expand_to_keypair(schnorrkel::ExpansionMode::Ed25519),
+			),
+			CryptoScheme::Ecdsa => Self::Ecdsa(
+				k256::ecdsa::SigningKey::from_bytes(seed.into())
+					.map_err(|_| "seed is not a valid secp256k1 secret key")?,
+			),
+		})
+	}
+
+	pub fn public(&self) -> MultiSigner {
+		match self {
+			Self::Ed25519(sk) => MultiSigner::Ed25519(*sk.verifying_key().as_bytes()),
+			Self::Sr25519(kp) => MultiSigner::Sr25519(kp.public.to_bytes()),
+			Self::Ecdsa(sk) => {
+				let mut pk = [0u8; 33];
+				pk.copy_from_slice(sk.verifying_key().to_encoded_point(true).as_bytes());
+				MultiSigner::Ecdsa(pk)
+			},
+		}
+	}
+
+	pub fn sign(&self, msg: &[u8]) -> MultiSignature {
+		match self {
+			Self::Ed25519(sk) => {
+				use ed25519_dalek::Signer;
+				MultiSignature::Ed25519(sk.sign(msg).to_bytes())
+			},
+			Self::Sr25519(kp) => {
+				MultiSignature::Sr25519(kp.sign_simple(SR25519_CONTEXT, msg).to_bytes())
+			},
+			Self::Ecdsa(sk) => {
+				let (sig, v) = sk
+					.sign_prehash_recoverable(&blake2_256(msg))
+					.expect("signing a 32-byte prehash cannot fail");
+				let mut out = [0u8; 65];
+				out[..64].copy_from_slice(&sig.to_bytes());
+				out[64] = v.to_byte();
+				MultiSignature::Ecdsa(out)
+			},
+		}
+	}
+}
+
+impl From<ed25519_dalek::SigningKey> for Pair {
+	fn from(sk: ed25519_dalek::SigningKey) -> Self {
+		Self::Ed25519(sk)
+	}
+}
+
+/// Anything that can sign extrinsic payloads for one account: an in-memory `Pair`, a key file,
+/// or an external signer process (see `crate::signer`). Fallible because the key may live
+/// outside this process.
+pub trait Signer {
+	fn public(&self) -> Result<MultiSigner, String>;
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, String>;
+}
+
+impl Signer for Pair {
+	fn public(&self) -> Result<MultiSigner, String> {
+		Ok(Pair::public(self))
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, String> {
+		Ok(Pair::sign(self, payload))
+	}
+}
+
+/// How long an extrinsic stays valid once signed, in milliseconds: clients set `valid_until` this
+/// far past their clock.
+pub const EXTRINSIC_LIFETIME_MILLIS: u64 = 5 * 60 * 1000;
+
 #[derive(Encode, Decode)]
 pub struct UncheckedExtrinsic<Call> {
-	/// Ed25519 public key of the sender
+	/// Account of the sender; the public key itself for ed25519/sr25519.
 	pub signer: AccountId32,
-	/// Ed25519 signature over SCALE(`signer.0 ‖ nonce ‖ call`).
-	pub signature: [u8; 64],
+	/// Signature over SCALE(`signer.0 ‖ nonce ‖ valid_until ‖ call`) in the sender's scheme.
+	pub signature: MultiSignature,
 	pub nonce: u32,
+	/// Latest `Header::timestamp` of a block that may include the extrinsic; later blocks skip it.
+	pub valid_until: u64,
 	pub call: Call,
 }
 
 impl<Call: Encode> UncheckedExtrinsic<Call> {
-		pub fn new_signed(sk: &ed25519_dalek::SigningKey, nonce: u32, call: Call) -> Self {
-		use ed25519_dalek::Signer;
-		let signer = AccountId32(*sk.verifying_key().as_bytes());
-		let payload = (signer.as_bytes(), nonce, &call).encode();
-		let signature = sk.sign(&payload).to_bytes();
-		Self { signer, signature, nonce, call }
+	/// Sign with an in-memory key, which cannot fail.
+	pub fn new_signed(pair: &Pair, nonce: u32, valid_until: u64, call: Call) -> Self {
+		Self::sign_with(pair, nonce, valid_until, call).expect("in-memory signing cannot fail")
+	}
+
+	/// Sign with any `Signer`, e.g. a key file or a signer daemon.
+	pub fn sign_with<S: Signer + ?Sized>(
+		signer: &S,
+		nonce: u32,
+		valid_until: u64,
+		call: Call,
+	) -> Result<Self, String> {
+		let account = signer.public()?.into_account();
+		let payload = (account.as_bytes(), nonce, valid_until, &call).encode();
+		let signature = signer.sign(&payload)?;
+		Ok(Self { signer: account, signature, nonce, valid_until, call })
+	}
+
+	/// The bytes the signature covers.
+	fn payload(&self) -> Vec<u8> {
+		(self.signer.as_bytes(), self.nonce, self.valid_until, &self.call).encode()
 	}
 
 	pub fn verify(&self) -> DispatchResult {
-		use ed25519_dalek::Verifier;
-		let vk = ed25519_dalek::VerifyingKey::from_bytes(self.signer.as_bytes())
-			.map_err(|_| "invalid public key")?;
-		let sig = ed25519_dalek::Signature::from_bytes(&self.signature);
-		let payload = (self.signer.as_bytes(), self.nonce, &self.call).encode();
-		vk.verify(&payload, &sig).map_err(|_| "invalid signature")
+		self.signature.verify(&self.payload(), &self.signer)
+	}
+
+	/// Canonical identifier: `blake2_256` of the full SCALE encoding, so any change to signer,
+	/// signature, nonce or call yields a different hash. Mirrors Substrate's extrinsic hash.
+	pub fn hash(&self) -> [u8; 32] {
+		blake2_256(&self.encode())
 	}
 }
 
 impl<Call: fmt::Debug> fmt::Debug for UncheckedExtrinsic<Call> {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		let sig_hex: String = self.signature.iter().map(|b| format!("{b:02x}")).collect();
 		f.debug_struct("UncheckedExtrinsic")
 			.field("signer", &self.signer)
-			.field("signature", &format!("0x{}…{}", &sig_hex[..8], &sig_hex[120..]))
+			.field("signature", &self.signature)
 			.field("nonce", &self.nonce)
+			.field("valid_until", &self.valid_until)
 			.field("call", &self.call)
 			.finish()
 	}
 }
 
-/// Verify all extrinsics in parallel using Rayon. Returns one `DispatchResult` per extrinsic
-/// in the same order. This mirrors a block-author's ability to pipeline signature checks
-/// across CPU cores before sequential state-transition.
+/// Verify every extrinsic of a block. Returns one `DispatchResult` per extrinsic in the same
+/// order.
+///
+/// Ed25519 signatures are checked together with `ed25519_dalek::verify_batch`, which folds the
+/// whole block into a single multiscalar multiplication via a random linear combination. A batch
+/// only says whether *all* signatures are valid, so if it fails the ed25519 subset is re-checked
+/// one by one to find the bad extrinsics. Keys and signatures the batch and single equations
+/// could disagree on are rejected up front, so the result never depends on the rest of the
+/// batch. Sr25519 and ecdsa signatures, and payload encoding, are handled in parallel with Rayon
+/// as before.
 pub fn verify_batch<Call>(exts: &[UncheckedExtrinsic<Call>]) -> Vec<DispatchResult>
+where
+	Call: Encode + Sync,
+{
+	verify_refs(&exts.iter().collect::<Vec<_>>())
+}
+
+/// Like `verify_batch`, but skips extrinsics whose hash is in `cache`, i.e. whose signature was
+/// already checked when the mempool admitted them. Hits are removed from the cache since an
+/// extrinsic is only ever imported once.
+pub fn verify_batch_cached<Call>(
+	exts: &[UncheckedExtrinsic<Call>],
+	cache: &Mutex<VerifiedCache>,
+) -> Vec<DispatchResult>
+where
+	Call: Encode + Sync,
+{
+	let hashes: Vec<[u8; 32]> = exts.iter().map(UncheckedExtrinsic::hash).collect();
+	let hits: Vec<bool> = {
+		let mut cache = cache.lock().expect("verified cache poisoned");
+		hashes.iter().map(|h| cache.take(h)).collect()
+	};
+
+	let misses: Vec<_> = exts.iter().zip(&hits).filter(|(_, hit)| !**hit).map(|(e, _)| e).collect();
+	let mut verified = verify_refs(&misses).into_iter();
+	hits.into_iter()
+		.map(|hit| if hit { Ok(()) } else { verified.next().expect("one result per miss") })
+		.collect()
+}
+
+fn verify_refs<Call>(exts: &[&UncheckedExtrinsic<Call>]) -> Vec<DispatchResult>
+where
+	Call: Encode + Sync,
+{
+	use rayon::prelude::*;
+
+	let payloads: Vec<Vec<u8>> = exts.par_iter().map(|e| e.payload()).collect();
+
+	// Non-ed25519 entries are verified individually; ed25519 ones are only decoded and checked
+	// by `ed25519_parts` here, and collected for the batch.
+	let checked: Vec<Result<Option<_>, &'static str>> = exts
+		.par_iter()
+		.zip(&payloads)
+		.map(|(e, payload)| match &e.signature {
+			MultiSignature::Ed25519(sig) => ed25519_parts(sig, &e.signer).map(Some),
+			other => other.verify(payload, &e.signer).map(|()| None),
+		})
+		.collect();
+	let mut results: Vec<DispatchResult> = checked.iter().map(|r| r.map(|_| ())).collect();
+	let batch: Vec<_> = checked
+		.into_iter()
+		.enumerate()
+		.filter_map(|(i, r)| r.ok().flatten().map(|(sig, vk)| (i, sig, vk)))
+		.collect();
+
+	if batch.is_empty() {
+		return results;
+	}
+
+	let messages: Vec<&[u8]> = batch.iter().map(|(i, ..)| payloads[*i].as_slice()).collect();
+	let signatures: Vec<_> = batch.iter().map(|(_, sig, _)| *sig).collect();
+	let keys: Vec<_> = batch.iter().map(|(.., vk)| *vk).collect();
+	if ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_err() {
+		let fallback: Vec<(usize, DispatchResult)> = batch
+			.par_iter()
+			.map(|(i, ..)| (*i, exts[*i].signature.verify(&payloads[*i], &exts[*i].signer)))
+			.collect();
+		for (i, res) in fallback {
+			results[i] = res;
+		}
+	}
+	results
+}
+
+/// Verify every extrinsic individually in parallel using Rayon, without batching. This is the
+/// fallback path of `verify_batch` and the baseline it is benchmarked against.
+pub fn verify_each<Call>(exts: &[UncheckedExtrinsic<Call>]) -> Vec<DispatchResult>
 where
 	Call: Encode + Sync,
 {
@@ -104,13 +579,163 @@ where
 	exts.par_iter().map(|e| e.verify()).collect()
 }
 
+/// Upper bound on cached extrinsic hashes; the oldest entry is evicted once it is reached.
+pub const VERIFIED_CACHE_CAPACITY: usize = 16_384;
+
+/// Bounded set of hashes of extrinsics whose signatures are known to be valid.
+///
+/// The node fills it when admitting an extrinsic into the mempool, and `execute_block` consults
+/// it so that transactions seen over RPC or gossip are not verified a second time on import.
+#[derive(Debug)]
+pub struct VerifiedCache {
+	capacity: usize,
+	/// Insertion order, tagged with the entry's insertion number. `take` leaves its hash here;
+	/// such stale slots no longer match `entries` and are skipped on eviction.
+	order: std::collections::VecDeque<([u8; 32], u64)>,
+	entries: std::collections::HashMap<[u8; 32], u64>,
+	inserted: u64,
+}
+
+impl VerifiedCache {
+	pub fn new(capacity: usize) -> Self {
+		Self { capacity, order: Default::default(), entries: Default::default(), inserted: 0 }
+	}
+
+	/// Record `ext` as verified, evicting the oldest entry when full.
+	pub fn insert<Call: Encode>(&mut self, ext: &UncheckedExtrinsic<Call>) {
+		let hash = ext.hash();
+		if self.capacity == 0 || self.entries.contains_key(&hash) {
+			return;
+		}
+		self.inserted += 1;
+		self.entries.insert(hash, self.inserted);
+		self.order.push_back((hash, self.inserted));
+		while self.entries.len() > self.capacity {
+			if let Some((old, n)) = self.order.pop_front() {
+				if self.entries.get(&old) == Some(&n) {
+					self.entries.remove(&old);
+				}
+			}
+		}
+		// Drop stale slots once they outnumber live ones, so `order` stays O(capacity).
+		if self.order.len() > 2 * self.capacity {
+			let entries = &self.entries;
+			self.order.retain(|(h, n)| entries.get(h) == Some(n));
+		}
+	}
+
+	pub fn contains<Call: Encode>(&self, ext: &UncheckedExtrinsic<Call>) -> bool {
+		self.entries.contains_key(&ext.hash())
+	}
+
+	/// Remove `hash`, returning whether it was present.
+	fn take(&mut self, hash: &[u8; 32]) -> bool {
+		self.entries.remove(hash).is_some()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+}
+
+static VERIFIED_CACHE: Lazy<Mutex<VerifiedCache>> =
+	Lazy::new(|| Mutex::new(VerifiedCache::new(VERIFIED_CACHE_CAPACITY)));
+
+/// Process-wide cache shared by mempool admission and block import.
+pub fn verified_cache() -> &'static Mutex<VerifiedCache> {
+	&VERIFIED_CACHE
+}
+
 pub type DispatchResult = Result<(), &'static str>;
 
+const PREFIX_EXTRINSIC_INDEX: &[u8] = b"index:extrinsic:";
+
+/// What happened to an extrinsic included in a block.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum ExtrinsicOutcome {
+	/// Dispatched successfully.
+	Applied,
+	/// Dispatched, but the call returned an error. The nonce was still consumed.
+	DispatchFailed(String),
+	/// Skipped because the signature did not verify.
+	BadSignature(String),
+	/// Skipped because the nonce did not match the signer's account nonce.
+	BadNonce,
+	/// Skipped because the block's timestamp is past the extrinsic's `valid_until`.
+	Expired,
+	/// Skipped because the same extrinsic was already dispatched: a replay, e.g. of an account
+	/// that was reaped and funded again, restarting its nonce.
+	Duplicate,
+}
+
+impl ExtrinsicOutcome {
+	/// Whether the extrinsic was dispatched, as opposed to skipped.
+	pub fn is_dispatched(&self) -> bool {
+		matches!(self, Self::Applied | Self::DispatchFailed(_))
+	}
+}
+
+/// Where and how an extrinsic was included, stored under `index:extrinsic:<hash>` by
+/// `execute_block` so RPC clients can look transactions up by hash.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct ExtrinsicRecord<BlockNumber> {
+	pub block_number: BlockNumber,
+	/// Position of the extrinsic within its block.
+	pub index: u32,
+	pub outcome: ExtrinsicOutcome,
+	/// SCALE-encoded extrinsic, kept so the call can be decoded without a block store.
+	pub extrinsic: Vec<u8>,
+}
+
+fn extrinsic_index_key(hash: &[u8; 32]) -> Vec<u8> {
+	let mut key = PREFIX_EXTRINSIC_INDEX.to_vec();
+	key.extend(hash);
+	key
+}
+
+/// Index `record` under `hash`. A dispatched extrinsic keeps its record: a replayed copy of it,
+/// which is skipped, must not hide where it was applied.
+pub fn record_extrinsic<BlockNumber: Encode + Decode>(
+	hash: &[u8; 32],
+	record: &ExtrinsicRecord<BlockNumber>,
+) {
+	if extrinsic_record::<BlockNumber>(hash).is_some_and(|r| r.outcome.is_dispatched()) {
+		return;
+	}
+	if let Err(e) = kv_store().put(&extrinsic_index_key(hash), &record.encode()) {
+		eprintln!("Failed to persist extrinsic index: {e}");
+	}
+}
+
+pub fn extrinsic_record<BlockNumber: Decode>(
+	hash: &[u8; 32],
+) -> Option<ExtrinsicRecord<BlockNumber>> {
+	kv_store()
+		.get(&extrinsic_index_key(hash))
+		.and_then(|bytes| ExtrinsicRecord::decode(&mut &bytes[..]).ok())
+}
+
 pub trait KeyValueStore {
 	fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
 	fn put(&self, key: &[u8], value: &[u8]) -> Result<(), String>;
 	fn delete(&self, key: &[u8]) -> Result<(), String>;
 	fn scan_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)>;
+
+	/// Apply `changes`, where `None` deletes the key. Backends that can write them atomically
+	/// override this.
+	fn write_batch(&self, changes: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String> {
+		for (key, value) in changes {
+			match value {
+				Some(value) => self.put(&key, &value)?,
+				None => self.delete(&key)?,
+			}
+		}
+		Ok(())
+	}
 }
 
 static ROCKS_DB: Lazy<DB> = Lazy::new(|| {
@@ -147,11 +772,121 @@ impl KeyValueStore for RocksDbStore {
 			)
 			.collect()
 	}
+
+	fn write_batch(&self, changes: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<(), String> {
+		let mut batch = WriteBatch::default();
+		for (key, value) in changes {
+			match value {
+				Some(value) => batch.put(key, value),
+				None => batch.delete(key),
+			}
+		}
+		ROCKS_DB.write(batch).map_err(|e| e.to_string())
+	}
+}
+
+/// Storage writes buffered by `with_storage_layer`; `None` marks a deletion.
+type StorageLayer = std::collections::BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+thread_local! {
+	/// This thread's open `with_storage_layer` layers, innermost last.
+	static STORAGE_LAYERS: std::cell::RefCell<Vec<StorageLayer>> =
+		const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// What `kv_store` returns: the backend seen through this thread's open storage layers. Reads
+/// see buffered writes, and writes go to the innermost layer while one is open.
+pub struct LayeredStore<S>(S);
+
+impl<S: KeyValueStore> LayeredStore<S> {
+	/// Buffer a write in the innermost layer. Returns `false` if no layer is open.
+	fn buffer(&self, key: &[u8], value: Option<&[u8]>) -> bool {
+		STORAGE_LAYERS.with(|layers| match layers.borrow_mut().last_mut() {
+			Some(layer) => {
+				layer.insert(key.to_vec(), value.map(<[u8]>::to_vec));
+				true
+			},
+			None => false,
+		})
+	}
+}
+
+impl<S: KeyValueStore> KeyValueStore for LayeredStore<S> {
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let buffered = STORAGE_LAYERS
+			.with(|layers| layers.borrow().iter().rev().find_map(|layer| layer.get(key).cloned()));
+		match buffered {
+			Some(value) => value,
+			None => self.0.get(key),
+		}
+	}
+
+	fn put(&self, key: &[u8], value: &[u8]) -> Result<(), String> {
+		if self.buffer(key, Some(value)) { Ok(()) } else { self.0.put(key, value) }
+	}
+
+	fn delete(&self, key: &[u8]) -> Result<(), String> {
+		if self.buffer(key, None) { Ok(()) } else { self.0.delete(key) }
+	}
+
+	fn scan_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let mut entries: std::collections::BTreeMap<_, _> =
+			self.0.scan_prefix(prefix).into_iter().collect();
+		STORAGE_LAYERS.with(|layers| {
+			for layer in layers.borrow().iter() {
+				let buffered =
+					layer.range(prefix.to_vec()..).take_while(|(k, _)| k.starts_with(prefix));
+				for (key, value) in buffered {
+					match value {
+						Some(value) => entries.insert(key.clone(), value.clone()),
+						None => entries.remove(key),
+					};
+				}
+			}
+		});
+		entries.into_iter().collect()
+	}
+}
+
+/// Run `f` with this thread's storage writes buffered in a new layer, kept only if it returns
+/// `Ok`: merged into the enclosing layer, or else written to the backend in one batch. On `Err`
+/// the writes are dropped. Pallets' in-memory state is not covered; callers reload it.
+pub fn with_storage_layer<R, E>(f: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
+	// Drops the layer if `f` panics, so later writes on this thread are not buffered forever.
+	struct PopOnUnwind;
+	impl Drop for PopOnUnwind {
+		fn drop(&mut self) {
+			if std::thread::panicking() {
+				STORAGE_LAYERS.with(|layers| layers.borrow_mut().pop());
+			}
+		}
+	}
+
+	STORAGE_LAYERS.with(|layers| layers.borrow_mut().push(StorageLayer::new()));
+	let guard = PopOnUnwind;
+	let result = f();
+	std::mem::forget(guard);
+	let layer = STORAGE_LAYERS.with(|layers| layers.borrow_mut().pop()).expect("pushed above");
+	if result.is_ok() {
+		let outermost = STORAGE_LAYERS.with(|layers| match layers.borrow_mut().last_mut() {
+			Some(outer) => {
+				outer.extend(layer);
+				None
+			},
+			None => Some(layer),
+		});
+		if let Some(layer) = outermost {
+			if let Err(e) = kv_store().0.write_batch(layer.into_iter().collect()) {
+				eprintln!("Failed to persist storage changes: {e}");
+			}
+		}
+	}
+	result
 }
 
 #[cfg(not(test))]
-pub fn kv_store() -> RocksDbStore {
-	RocksDbStore
+pub fn kv_store() -> LayeredStore<RocksDbStore> {
+	LayeredStore(RocksDbStore)
 }
 
 /// In-memory store used by all unit tests.
@@ -160,8 +895,51 @@ pub fn kv_store() -> RocksDbStore {
 /// so the thread-local gives every test a completely isolated, zero-initialised store —
 /// no RocksDB, no leftover chain state bleeding between tests.
 #[cfg(test)]
-pub fn kv_store() -> test_store::MemStore {
-	test_store::MemStore
+pub fn kv_store() -> LayeredStore<test_store::MemStore> {
+	LayeredStore(test_store::MemStore)
+}
+
+/// Load every `prefix ++ SCALE(key)` entry from storage into a map, skipping entries that do
+/// not decode. Pallets use this to rebuild their in-memory state on start-up.
+pub fn load_prefix<K: Ord + Decode, V: Decode>(prefix: &[u8]) -> std::collections::BTreeMap<K, V> {
+	let mut map = std::collections::BTreeMap::new();
+	for (key, value) in kv_store().scan_prefix(prefix) {
+		if key.len() <= prefix.len() {
+			continue;
+		}
+		let key_bytes = &key[prefix.len()..];
+		if let (Ok(k), Ok(v)) = (K::decode(&mut &key_bytes[..]), V::decode(&mut &value[..])) {
+			map.insert(k, v);
+		}
+	}
+	map
+}
+
+/// Mirror `value` under `prefix ++ SCALE(key)` into `map` and storage; `None` removes both. The
+/// write side of `load_prefix`.
+pub fn write_prefix<K: Ord + Encode, V: Encode>(
+	map: &mut std::collections::BTreeMap<K, V>,
+	prefix: &[u8],
+	key: K,
+	value: Option<V>,
+) {
+	let mut storage_key = prefix.to_vec();
+	key.encode_to(&mut storage_key);
+	let what = String::from_utf8_lossy(prefix);
+	match value {
+		Some(value) => {
+			if let Err(e) = kv_store().put(&storage_key, &value.encode()) {
+				eprintln!("Failed to persist {what} entry: {e}");
+			}
+			map.insert(key, value);
+		},
+		None => {
+			map.remove(&key);
+			if let Err(e) = kv_store().delete(&storage_key) {
+				eprintln!("Failed to delete {what} entry: {e}");
+			}
+		},
+	}
 }
 
 #[cfg(test)]
@@ -311,19 +1089,336 @@ where
 	}
 }
 
+/// Who a call is dispatched as, like FRAME's `RawOrigin`. Calls take it as their `origin` and
+/// check it with `ensure_signed`, `ensure_root` or `ensure_none`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum RuntimeOrigin<AccountId> {
+	/// An extrinsic signed by this account.
+	Signed(AccountId),
+	/// The chain itself, for privileged calls. No extrinsic is dispatched as root; only runtime
+	/// code can do that.
+	Root,
+	/// No one in particular, for calls the runtime makes without a signer.
+	None,
+}
+
+/// The account that signed `origin`, or an error for any other origin.
+pub fn ensure_signed<AccountId>(
+	origin: RuntimeOrigin<AccountId>,
+) -> Result<AccountId, &'static str> {
+	match origin {
+		RuntimeOrigin::Signed(who) => Ok(who),
+		_ => Err("bad origin: expected a signed origin"),
+	}
+}
+
+/// Succeeds only for `RuntimeOrigin::Root`.
+pub fn ensure_root<AccountId>(origin: RuntimeOrigin<AccountId>) -> DispatchResult {
+	match origin {
+		RuntimeOrigin::Root => Ok(()),
+		_ => Err("bad origin: expected root"),
+	}
+}
+
+/// Succeeds only for `RuntimeOrigin::None`.
+pub fn ensure_none<AccountId>(origin: RuntimeOrigin<AccountId>) -> DispatchResult {
+	match origin {
+		RuntimeOrigin::None => Ok(()),
+		_ => Err("bad origin: expected no origin"),
+	}
+}
+
+/// How deeply calls may nest inside an extrinsic, e.g. `sudo` wrapping another call. Decode
+/// extrinsics and blocks from peers with `DecodeLimit` and this limit, so a deeply nested call
+/// cannot overflow the stack.
+pub const MAX_EXTRINSIC_DEPTH: u32 = 256;
+
 pub trait Dispatch {
-	type Caller;
+	type Origin;
 	type Call;
 
-	fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult;
+	fn dispatch(&mut self, origin: Self::Origin, call: Self::Call) -> DispatchResult;
+}
+
+/// `Dispatch` for a pallet whose calls work on another pallet's state, such as its `Currency`.
+/// The runtime passes that pallet in as `context`, as named by the pallet field's
+/// `#[context(...)]` attribute.
+pub trait DispatchWithContext<Context> {
+	type Origin;
+	type Call;
+
+	fn dispatch(
+		&mut self,
+		context: &mut Context,
+		origin: Self::Origin,
+		call: Self::Call,
+	) -> DispatchResult;
+}
+
+/// A pallet whose calls may hand a runtime call back to be dispatched as root, such as `sudo`.
+/// Its runtime field is marked `#[dispatches_as_root]`: right after each of its calls, within the
+/// same dispatch, the runtime takes the call, dispatches it as `RuntimeOrigin::Root` and reports
+/// the result back.
+pub trait DispatchesAsRoot<RuntimeCall> {
+	/// Who the outcome is reported for, e.g. the sudo key that made the call.
+	type Caller;
+
+	/// The call the last dispatched call left to run as root, if any.
+	fn take_root_call(&mut self) -> Option<(Self::Caller, RuntimeCall)>;
+
+	/// How dispatching the call from `take_root_call` went.
+	fn root_call_dispatched(&mut self, caller: Self::Caller, result: DispatchResult);
+}
+
+/// Runtime-level glue that `execute_block` runs around dispatch, for work spanning pallets that
+/// cannot reach each other's state (e.g. clearing the `system` nonce of an account `balances`
+/// reaped). Implemented by the runtime.
+pub trait RuntimeHooks {
+	/// Runs once per block, after the block number is bumped and before any extrinsic.
+	fn on_initialize(&mut self) {}
+
+	/// Runs after every dispatched extrinsic, whether the dispatch succeeded or not.
+	fn after_dispatch(&mut self) {}
+
+	/// Merkle root of the state a block commits to in `Header::state_root`, computed once all its
+	/// extrinsics have run.
+	fn state_root(&self) -> [u8; 32];
+}
+
+/// Whether a withdrawal may take an account below the existential deposit, reaping it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExistenceRequirement {
+	KeepAlive,
+	AllowDeath,
+}
+
+/// Where `Currency::repatriate_reserved` credits the moved funds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BalanceStatus {
+	Free,
+	Reserved,
+}
+
+/// Names a balance lock, so several pallets can lock the same account independently.
+pub type LockIdentifier = [u8; 8];
+
+/// Balance operations a pallet needs from another, like Substrate's `Currency`,
+/// `ReservableCurrency` and `LockableCurrency` folded into one. Pallets name the implementation
+/// in their `Config` (`type Currency: Currency<Self::AccountId, ...>`); `balances::Pallet`
+/// provides it.
+///
+/// An account's free balance is what it can spend; its reserved balance is held aside (e.g. as a
+/// deposit) and can only be unreserved, slashed or repatriated. Locks do not move funds: they
+/// freeze part of the free balance so it cannot be withdrawn. Overlapping locks do not add up —
+/// the largest one applies.
+pub trait Currency<AccountId> {
+	type Balance;
+	type BlockNumber;
+
+	fn free_balance(&self, who: &AccountId) -> Self::Balance;
+
+	fn reserved_balance(&self, who: &AccountId) -> Self::Balance;
+
+	fn transfer(
+		&mut self,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+		existence: ExistenceRequirement,
+	) -> DispatchResult;
+
+	/// Move `amount` from free to reserved. Fails if the free balance, less any locks, is short.
+	fn reserve(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+	/// Move up to `amount` from reserved back to free. Returns the part that was not reserved.
+	fn unreserve(&mut self, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+
+	/// Destroy up to `amount` of reserved funds, reducing total issuance. Returns the part that
+	/// was not reserved.
+	fn slash_reserved(&mut self, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+
+	/// Move up to `amount` of `slashed`'s reserved funds to `beneficiary`'s free or reserved
+	/// balance. Returns the part that was not reserved.
+	fn repatriate_reserved(
+		&mut self,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		amount: Self::Balance,
+		status: BalanceStatus,
+	) -> Result<Self::Balance, &'static str>;
+
+	/// Create or replace the lock `id` on `who`. With `until`, the lock stops applying from that
+	/// block on.
+	fn set_lock(
+		&mut self,
+		id: LockIdentifier,
+		who: &AccountId,
+		amount: Self::Balance,
+		until: Option<Self::BlockNumber>,
+	);
+
+	fn remove_lock(&mut self, id: LockIdentifier, who: &AccountId);
 }
 
 /// Dev keyring — mirrors `sp_keyring::AccountKeyring` from the Substrate ecosystem.
 ///
-/// Each variant derives a deterministic Ed25519 key from the UTF-8 encoding of the
-/// variant name zero-padded to 32 bytes. Only for development / testing.
+/// Keys derive from secret URIs as in Substrate: Alice is `//Alice` from `DEV_PHRASE`, and any
+/// BIP39 mnemonic with a derivation path names further deterministic accounts. The same URI
+/// yields one account per `CryptoScheme`. Only for development / testing.
 pub mod keyring {
-	use ed25519_dalek::SigningKey;
+	use super::{AccountId32, CryptoScheme, Pair, blake2_256};
+	use parity_scale_codec::Encode;
+	use std::sync::OnceLock;
+	use zeroize::Zeroizing;
+
+	/// Phrase behind the dev accounts, the same as Substrate's, so `//Alice` here is `//Alice`
+	/// there. A secret URI with no phrase uses it.
+	pub const DEV_PHRASE: &str =
+		"bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+
+	/// One step of a derivation path, holding its 32-byte chain code. Mirrors
+	/// `sp_core::crypto::DeriveJunction`.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum DeriveJunction {
+		/// `//code`: the child secret cannot be linked to the parent's public key.
+		Hard([u8; 32]),
+		/// `/code`: the child public key is derivable from the parent's public key (sr25519 only).
+		Soft([u8; 32]),
+	}
+
+	impl DeriveJunction {
+		/// The chain code is the SCALE encoding of `code` (as a `u64` if numeric), zero-padded,
+		/// or its `blake2_256` if longer than 32 bytes.
+		pub fn new(code: &str, hard: bool) -> Self {
+			let encoded = match code.parse::<u64>() {
+				Ok(n) => n.encode(),
+				Err(_) => code.encode(),
+			};
+			let mut cc = [0u8; 32];
+			if encoded.len() > 32 {
+				cc = blake2_256(&encoded);
+			} else {
+				cc[..encoded.len()].copy_from_slice(&encoded);
+			}
+			if hard { Self::Hard(cc) } else { Self::Soft(cc) }
+		}
+	}
+
+	/// Secret URI `<phrase | 0x seed>[//hard | /soft]*[///password]`, e.g. `//Alice` or
+	/// `<mnemonic>//stash//0`. An empty phrase means `DEV_PHRASE`; the password is the BIP39
+	/// passphrase. Mirrors `sp_core::crypto::SecretUri`.
+	#[derive(Clone)]
+	pub struct SecretUri {
+		seed: Zeroizing<[u8; 32]>,
+		junctions: Vec<DeriveJunction>,
+	}
+
+	impl core::str::FromStr for SecretUri {
+		type Err = String;
+
+		fn from_str(s: &str) -> Result<Self, Self::Err> {
+			let (rest, password) = match s.split_once("///") {
+				Some((rest, password)) => (rest, Some(password)),
+				None => (s, None),
+			};
+			let (phrase, mut path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+			let mut junctions = Vec::new();
+			while !path.is_empty() {
+				let hard = path.starts_with("//");
+				path = &path[if hard { 2 } else { 1 }..];
+				let end = path.find('/').unwrap_or(path.len());
+				if end == 0 {
+					return Err(format!("empty derivation junction in '{s}'"));
+				}
+				junctions.push(DeriveJunction::new(&path[..end], hard));
+				path = &path[end..];
+			}
+
+			let phrase = phrase.trim();
+			let seed = if let Some(hex) = phrase.strip_prefix("0x") {
+				if password.is_some() {
+					return Err("a password is only valid with a mnemonic".into());
+				}
+				let mut seed = Zeroizing::new([0u8; 32]);
+				hex::decode_to_slice(hex, &mut *seed)
+					.map_err(|_| "seed must be 32 bytes of hex".to_string())?;
+				seed
+			} else if phrase.is_empty() && password.is_none() {
+				dev_seed()
+			} else {
+				seed_from_phrase(if phrase.is_empty() { DEV_PHRASE } else { phrase }, password)?
+			};
+			Ok(Self { seed, junctions })
+		}
+	}
+
+	impl SecretUri {
+		/// Derive the key of `scheme`: sr25519 follows schnorrkel's hard and soft derivation;
+		/// ed25519 and ecdsa only support hard junctions, each replacing the seed with
+		/// `blake2_256(SCALE("<Scheme>HDKD", seed, chain_code))`.
+		pub fn pair(&self, scheme: CryptoScheme) -> Result<Pair, String> {
+			use schnorrkel::{
+				ExpansionMode, MiniSecretKey,
+				derive::{ChainCode, Derivation},
+			};
+
+			if scheme == CryptoScheme::Sr25519 {
+				let mut secret = MiniSecretKey::from_bytes(&*self.seed)
+					.expect("32-byte seed is a valid mini secret key")
+					.expand(ExpansionMode::Ed25519);
+				for junction in &self.junctions {
+					secret = match junction {
+						DeriveJunction::Hard(cc) => secret
+							.hard_derive_mini_secret_key(Some(ChainCode(*cc)), b"")
+							.0
+							.expand(ExpansionMode::Ed25519),
+						DeriveJunction::Soft(cc) => secret.derived_key_simple(ChainCode(*cc), []).0,
+					};
+				}
+				return Ok(Pair::Sr25519(secret.to_keypair()));
+			}
+
+			let id = match scheme {
+				CryptoScheme::Ecdsa => "Secp256k1HDKD",
+				_ => "Ed25519HDKD",
+			};
+			let mut seed = self.seed.clone();
+			for junction in &self.junctions {
+				match junction {
+					DeriveJunction::Hard(cc) => *seed = blake2_256(&(id, *seed, *cc).encode()),
+					DeriveJunction::Soft(_) =>
+						return Err(format!("{scheme} keys only support hard (//) derivation")),
+				}
+			}
+			Pair::try_from_seed(scheme, &seed)
+		}
+	}
+
+	/// BIP39 seed of a mnemonic as Substrate computes it: PBKDF2-HMAC-SHA512 over the phrase's
+	/// entropy (not its words) with salt `"mnemonic" ‖ password`, truncated to 32 bytes.
+	fn seed_from_phrase(
+		phrase: &str,
+		password: Option<&str>,
+	) -> Result<Zeroizing<[u8; 32]>, String> {
+		let mnemonic = bip39::Mnemonic::parse_in_normalized(bip39::Language::English, phrase)
+			.map_err(|e| format!("invalid mnemonic: {e}"))?;
+		let entropy = Zeroizing::new(mnemonic.to_entropy());
+		let salt = format!("mnemonic{}", password.unwrap_or(""));
+		let mut full = Zeroizing::new([0u8; 64]);
+		pbkdf2::pbkdf2_hmac::<sha2::Sha512>(&entropy, salt.as_bytes(), 2048, &mut *full);
+		let mut seed = Zeroizing::new([0u8; 32]);
+		seed.copy_from_slice(&full[..32]);
+		Ok(seed)
+	}
+
+	/// `DEV_PHRASE` seed, computed once: every dev-account key derivation starts here.
+	fn dev_seed() -> Zeroizing<[u8; 32]> {
+		static SEED: OnceLock<[u8; 32]> = OnceLock::new();
+		Zeroizing::new(*SEED.get_or_init(|| {
+			*seed_from_phrase(DEV_PHRASE, None).expect("DEV_PHRASE is a valid mnemonic")
+		}))
+	}
 
 	#[derive(Clone, Copy, Debug)]
 	pub enum AccountKeyring {
@@ -333,32 +1428,40 @@ pub mod keyring {
 	}
 
 	impl AccountKeyring {
-		fn seed(self) -> [u8; 32] {
-			let mut seed = [0u8; 32];
-			let name: &[u8] = match self {
-				Self::Alice => b"Alice",
-				Self::Bob => b"Bob",
-				Self::Charlie => b"Charlie",
-			};
-			seed[..name.len()].copy_from_slice(name);
-			seed
+		pub const ALL: [AccountKeyring; 3] = [Self::Alice, Self::Bob, Self::Charlie];
+
+		/// `//Alice`, `//Bob`, `//Charlie`.
+		pub fn uri(self) -> SecretUri {
+			format!("//{self:?}").parse().expect("dev URIs are valid")
+		}
+
+		/// Ed25519 key, the default scheme.
+		pub fn signing_key(self) -> Pair {
+			self.signing_key_for(CryptoScheme::Ed25519)
 		}
 
-		pub fn signing_key(self) -> SigningKey {
-			SigningKey::from_bytes(&self.seed())
+		pub fn signing_key_for(self, scheme: CryptoScheme) -> Pair {
+			self.uri().pair(scheme).expect("dev accounts only use hard junctions")
 		}
 
-		pub fn public(self) -> super::AccountId32 {
-			super::AccountId32(*self.signing_key().verifying_key().as_bytes())
+		/// Ed25519 account id, the default scheme.
+		pub fn public(self) -> AccountId32 {
+			self.public_for(CryptoScheme::Ed25519)
+		}
+
+		pub fn public_for(self, scheme: CryptoScheme) -> AccountId32 {
+			self.signing_key_for(scheme).public().into_account()
 		}
 	}
 
-	pub fn from_name(name: &str) -> Option<AccountKeyring> {
+	/// Resolve a dev account name (`alice`, `bob`, `charlie`) or any secret URI, e.g. `//Dave`
+	/// or `<mnemonic>//stash//0`.
+	pub fn from_name(name: &str) -> Option<SecretUri> {
 		match name.to_lowercase().as_str() {
-			"alice" => Some(AccountKeyring::Alice),
-			"bob" => Some(AccountKeyring::Bob),
-			"charlie" => Some(AccountKeyring::Charlie),
-			_ => None,
+			"alice" => Some(AccountKeyring::Alice.uri()),
+			"bob" => Some(AccountKeyring::Bob.uri()),
+			"charlie" => Some(AccountKeyring::Charlie.uri()),
+			_ => name.parse().ok(),
 		}
 	}
 }
@@ -468,6 +1571,112 @@ mod tests {
 		assert_eq!(items, vec![&10, &20]);
 	}
 
+	// -----------------------------------------------------------------------
+	// AccountId32 — SS58 addresses
+	// -----------------------------------------------------------------------
+
+	#[test]
+	fn ss58_matches_substrate_encoding() {
+		let zero = AccountId32([0u8; 32]);
+		assert_eq!(zero.to_string(), "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM");
+	}
+
+	#[test]
+	fn ss58_roundtrip() {
+		let alice = Alice.public();
+		assert_eq!(alice.to_string().parse::<AccountId32>(), Ok(alice));
+		for prefix in [0, 42, 63, 64, 2_254, 16_383] {
+			let address = alice.to_ss58check_with_prefix(prefix);
+			assert_eq!(AccountId32::from_ss58check_with_prefix(&address), Ok((alice, prefix)));
+		}
+	}
+
+	#[test]
+	fn ss58_rejects_bad_checksum() {
+		let mut address = Alice.public().to_string().into_bytes();
+		let last = address.len() - 1;
+		address[last] = if address[last] == b'1' { b'2' } else { b'1' };
+		let address = String::from_utf8(address).unwrap();
+		assert!(address.parse::<AccountId32>().is_err());
+	}
+
+	#[test]
+	fn ss58_rejects_other_network_prefix() {
+		let polkadot = Alice.public().to_ss58check_with_prefix(0);
+		assert!(polkadot.parse::<AccountId32>().is_err());
+	}
+
+	#[test]
+	fn account_parses_from_hex() {
+		let alice = Alice.public();
+		let hex = hex::encode(alice.as_bytes());
+		assert_eq!(hex.parse::<AccountId32>(), Ok(alice));
+		assert_eq!(format!("0x{hex}").parse::<AccountId32>(), Ok(alice));
+	}
+
+	// -----------------------------------------------------------------------
+	// keyring — secret URIs
+	// -----------------------------------------------------------------------
+
+	fn uri_public(uri: &str, scheme: CryptoScheme) -> MultiSigner {
+		uri.parse::<keyring::SecretUri>().unwrap().pair(scheme).unwrap().public()
+	}
+
+	#[test]
+	fn dev_accounts_match_substrate() {
+		assert_eq!(
+			Alice.public_for(CryptoScheme::Sr25519).to_string(),
+			"5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+		);
+		assert_eq!(
+			hex::encode(Alice.public().as_bytes()),
+			"88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee"
+		);
+		assert_eq!(
+			hex::encode(Alice.signing_key_for(CryptoScheme::Ecdsa).public().as_bytes()),
+			"020a1091341fe5664bfa1782d5e04779689068c916b04cb365ec3153755684d9a1"
+		);
+	}
+
+	#[test]
+	fn secret_uri_defaults_to_dev_phrase() {
+		let explicit = format!("{}//Alice", keyring::DEV_PHRASE);
+		for scheme in [CryptoScheme::Ed25519, CryptoScheme::Sr25519, CryptoScheme::Ecdsa] {
+			assert_eq!(uri_public(&explicit, scheme), uri_public("//Alice", scheme));
+		}
+		let alice = keyring::from_name("ALICE").unwrap();
+		assert_eq!(
+			alice.pair(CryptoScheme::Ed25519).unwrap().public().into_account(),
+			Alice.public()
+		);
+	}
+
+	#[test]
+	fn secret_uri_paths_and_passwords_give_distinct_keys() {
+		let keys = ["//Dave", "//Dave//0", "//Dave//1", "//Dave///pw", "//stash//0"]
+			.map(|uri| uri_public(uri, CryptoScheme::Ed25519));
+		for (i, a) in keys.iter().enumerate() {
+			assert!(keys[i + 1..].iter().all(|b| a != b));
+		}
+		assert_eq!(uri_public("//Dave//1", CryptoScheme::Ed25519), keys[2]);
+	}
+
+	#[test]
+	fn soft_derivation_is_sr25519_only() {
+		let uri: keyring::SecretUri = "//Alice/soft".parse().unwrap();
+		assert!(uri.pair(CryptoScheme::Sr25519).is_ok());
+		assert!(uri.pair(CryptoScheme::Ed25519).is_err());
+		assert!(uri.pair(CryptoScheme::Ecdsa).is_err());
+	}
+
+	#[test]
+	fn secret_uri_rejects_bad_input() {
+		assert!("bottom drive obey lake".parse::<keyring::SecretUri>().is_err());
+		assert!("//Alice//".parse::<keyring::SecretUri>().is_err());
+		assert!("0x1234//Alice".parse::<keyring::SecretUri>().is_err());
+		assert!(keyring::from_name("dave").is_none());
+	}
+
 	// -----------------------------------------------------------------------
 	// UncheckedExtrinsic — signing & verification
 	// -----------------------------------------------------------------------
@@ -475,10 +1684,15 @@ mod tests {
 	#[derive(Encode)]
 	struct TestCall(u32);
 
+	/// An extrinsic that never expires; these tests never put one in a block.
+	fn signed(pair: &Pair, nonce: u32, call: TestCall) -> UncheckedExtrinsic<TestCall> {
+		UncheckedExtrinsic::new_signed(pair, nonce, u64::MAX, call)
+	}
+
 	#[test]
 	fn new_signed_produces_valid_extrinsic() {
 		let sk = Alice.signing_key();
-		let ext = UncheckedExtrinsic::new_signed(&sk, 0, TestCall(42));
+		let ext = signed(&sk, 0, TestCall(42));
 		assert_eq!(ext.signer, Alice.public());
 		assert_eq!(ext.nonce, 0);
 		assert!(ext.verify().is_ok());
@@ -487,28 +1701,159 @@ mod tests {
 	#[test]
 	fn verify_rejects_tampered_nonce() {
 		let sk = Alice.signing_key();
-		let mut ext = UncheckedExtrinsic::new_signed(&sk, 0, TestCall(1));
+		let mut ext = signed(&sk, 0, TestCall(1));
 		ext.nonce = 99;
 		assert!(ext.verify().is_err());
 	}
 
+	#[test]
+	fn verify_rejects_extended_validity() {
+		let sk = Alice.signing_key();
+		let mut ext = UncheckedExtrinsic::new_signed(&sk, 0, 1_000, TestCall(1));
+		assert!(ext.verify().is_ok());
+		ext.valid_until = 2_000;
+		assert!(ext.verify().is_err());
+	}
+
 	#[test]
 	fn verify_rejects_wrong_signer_field() {
 		let sk = Alice.signing_key();
-		let mut ext = UncheckedExtrinsic::new_signed(&sk, 0, TestCall(1));
+		let mut ext = signed(&sk, 0, TestCall(1));
 		// Swap the signer field to Bob's public key — payload won't match.
 		ext.signer = Bob.public();
 		assert!(ext.verify().is_err());
 	}
 
+	#[test]
+	fn hash_is_deterministic_and_covers_nonce() {
+		let sk = Alice.signing_key();
+		let ext = signed(&sk, 0, TestCall(1));
+		assert_eq!(ext.hash(), signed(&sk, 0, TestCall(1)).hash());
+		assert_ne!(ext.hash(), signed(&sk, 1, TestCall(1)).hash());
+	}
+
+	#[test]
+	fn blake2_256_reader_matches_one_shot_hash() {
+		let data = vec![0xab; 20_000];
+		assert_eq!(blake2_256_reader(&data[..]).unwrap(), blake2_256(&data));
+	}
+
+	#[test]
+	fn parse_hash_accepts_hex_with_or_without_prefix() {
+		let hash = [7u8; 32];
+		assert_eq!(parse_hash(&format!("0x{}", hex::encode(hash))), Ok(hash));
+		assert_eq!(parse_hash(&hex::encode(hash)), Ok(hash));
+		assert_eq!(parse_hash("0x0707"), Err("hash must be 32 bytes".to_string()));
+		assert!(parse_hash("0xzz").is_err());
+	}
+
+	#[test]
+	fn storage_layers_keep_writes_only_on_success() {
+		let store = kv_store();
+		store.put(b"layer:a", b"1").unwrap();
+		store.put(b"layer:b", b"1").unwrap();
+
+		let failed: Result<(), ()> = with_storage_layer(|| {
+			store.put(b"layer:a", b"2").unwrap();
+			store.delete(b"layer:b").unwrap();
+			assert_eq!(store.get(b"layer:a"), Some(b"2".to_vec()), "reads see the layer");
+			assert_eq!(store.scan_prefix(b"layer:"), vec![(b"layer:a".to_vec(), b"2".to_vec())]);
+			Err(())
+		});
+		assert!(failed.is_err());
+		assert_eq!(store.get(b"layer:a"), Some(b"1".to_vec()));
+		assert_eq!(store.get(b"layer:b"), Some(b"1".to_vec()));
+
+		let ok = with_storage_layer(|| {
+			store.put(b"layer:c", b"3")?;
+			// A failing inner layer only drops its own writes.
+			let _ = with_storage_layer(|| {
+				store.delete(b"layer:a").and(Err::<(), _>("failed".to_string()))
+			});
+			with_storage_layer(|| store.delete(b"layer:b"))
+		});
+		assert!(ok.is_ok());
+		let keys: Vec<_> = store.scan_prefix(b"layer:").into_iter().map(|(k, _)| k).collect();
+		assert_eq!(keys, vec![b"layer:a".to_vec(), b"layer:c".to_vec()]);
+		assert!(test_store::MemStore.get(b"layer:c").is_some(), "written to the backend");
+	}
+
+	#[test]
+	fn extrinsic_record_roundtrip() {
+		let hash = [7u8; 32];
+		assert!(extrinsic_record::<u32>(&hash).is_none());
+		let record = ExtrinsicRecord {
+			block_number: 3u32,
+			index: 1,
+			outcome: ExtrinsicOutcome::DispatchFailed("Not enough funds.".into()),
+			extrinsic: vec![1, 2, 3],
+		};
+		record_extrinsic(&hash, &record);
+		let stored = extrinsic_record::<u32>(&hash).expect("recorded");
+		assert_eq!(stored.block_number, 3);
+		assert_eq!(stored.index, 1);
+		assert_eq!(stored.outcome, record.outcome);
+		assert_eq!(stored.extrinsic, vec![1, 2, 3]);
+	}
+
 	#[test]
 	fn nonces_produce_different_signatures() {
 		let sk = Alice.signing_key();
-		let ext0 = UncheckedExtrinsic::new_signed(&sk, 0, TestCall(1));
-		let ext1 = UncheckedExtrinsic::new_signed(&sk, 1, TestCall(1));
+		let ext0 = signed(&sk, 0, TestCall(1));
+		let ext1 = signed(&sk, 1, TestCall(1));
 		assert_ne!(ext0.signature, ext1.signature);
 	}
 
+	#[test]
+	fn sr25519_extrinsic_verifies() {
+		let pair = Alice.signing_key_for(CryptoScheme::Sr25519);
+		let ext = signed(&pair, 0, TestCall(7));
+		assert_eq!(ext.signer, Alice.public_for(CryptoScheme::Sr25519));
+		assert_eq!(ext.signature.scheme(), CryptoScheme::Sr25519);
+		assert!(ext.verify().is_ok());
+	}
+
+	#[test]
+	fn ecdsa_extrinsic_verifies_by_recovery() {
+		let pair = Alice.signing_key_for(CryptoScheme::Ecdsa);
+		let ext = signed(&pair, 0, TestCall(7));
+		assert_eq!(ext.signer, Alice.public_for(CryptoScheme::Ecdsa));
+		assert!(ext.verify().is_ok());
+	}
+
+	#[test]
+	fn ecdsa_account_is_blake2_of_compressed_key() {
+		let MultiSigner::Ecdsa(pk) = Alice.signing_key_for(CryptoScheme::Ecdsa).public() else {
+			panic!("expected an ecdsa public key");
+		};
+		assert_eq!(Alice.public_for(CryptoScheme::Ecdsa), AccountId32(blake2_256(&pk)));
+	}
+
+	#[test]
+	fn each_scheme_derives_a_distinct_account() {
+		let ed = Alice.public_for(CryptoScheme::Ed25519);
+		let sr = Alice.public_for(CryptoScheme::Sr25519);
+		let ecdsa = Alice.public_for(CryptoScheme::Ecdsa);
+		assert_ne!(ed, sr);
+		assert_ne!(ed, ecdsa);
+		assert_ne!(sr, ecdsa);
+	}
+
+	#[test]
+	fn verify_rejects_signer_of_other_scheme() {
+		// Each of Alice's keys signs, and the extrinsic claims her account under every other
+		// scheme derived from the same seed.
+		let schemes = [CryptoScheme::Ed25519, CryptoScheme::Sr25519, CryptoScheme::Ecdsa];
+		for signed_with in schemes {
+			let pair = Alice.signing_key_for(signed_with);
+			for claimed in schemes.into_iter().filter(|s| *s != signed_with) {
+				let mut ext = signed(&pair, 0, TestCall(1));
+				ext.signer = Alice.public_for(claimed);
+				assert!(ext.verify().is_err(), "{signed_with:?} signature, {claimed:?} signer");
+			}
+		}
+	}
+
 	// -----------------------------------------------------------------------
 	// verify_batch
 	// -----------------------------------------------------------------------
@@ -516,17 +1861,123 @@ mod tests {
 	#[test]
 	fn verify_batch_all_valid() {
 		let sk = Alice.signing_key();
-		let exts: Vec<_> =
-			(0..4).map(|n| UncheckedExtrinsic::new_signed(&sk, n, TestCall(n))).collect();
+		let exts: Vec<_> = (0..4).map(|n| signed(&sk, n, TestCall(n))).collect();
 		let results = verify_batch(&exts);
 		assert!(results.iter().all(|r| r.is_ok()));
 	}
 
+	#[test]
+	fn verify_batch_mixed_schemes() {
+		let exts: Vec<_> = [CryptoScheme::Ed25519, CryptoScheme::Sr25519, CryptoScheme::Ecdsa]
+			.into_iter()
+			.map(|scheme| signed(&Alice.signing_key_for(scheme), 0, TestCall(0)))
+			.collect();
+		assert!(verify_batch(&exts).iter().all(|r| r.is_ok()));
+	}
+
+	#[test]
+	fn verify_batch_agrees_with_verify_each() {
+		let sk = Alice.signing_key();
+		let mut exts: Vec<_> = (0..8).map(|n| signed(&sk, n, TestCall(n))).collect();
+		exts[3].nonce = 99;
+		exts[6].signer = Bob.public();
+		assert_eq!(verify_batch(&exts), verify_each(&exts));
+	}
+
+	#[test]
+	fn verify_batch_agrees_with_verify_each_on_small_order_keys() {
+		// The identity point as both key and `R`, with `s = 0`: it satisfies the cofactorless
+		// equation for any message, so only the small-order check tells it apart.
+		let mut identity = [0u8; 32];
+		identity[0] = 1;
+		let mut sig = [0u8; 64];
+		sig[..32].copy_from_slice(&identity);
+
+		let sk = Alice.signing_key();
+		let mut exts: Vec<_> = (0..4).map(|n| signed(&sk, n, TestCall(n))).collect();
+		exts[1].signer = AccountId32::from(identity);
+		exts[1].signature = MultiSignature::Ed25519(sig);
+
+		let results = verify_each(&exts);
+		assert_eq!(verify_batch(&exts), results);
+		assert!(results[1].is_err());
+		assert!(results.iter().enumerate().all(|(i, r)| i == 1 || r.is_ok()));
+	}
+
+	// -----------------------------------------------------------------------
+	// VerifiedCache
+	// -----------------------------------------------------------------------
+
+	#[test]
+	fn verified_cache_evicts_oldest_when_full() {
+		let sk = Alice.signing_key();
+		let exts: Vec<_> = (0..3).map(|n| signed(&sk, n, TestCall(n))).collect();
+		let mut cache = VerifiedCache::new(2);
+		for ext in &exts {
+			cache.insert(ext);
+		}
+		assert_eq!(cache.len(), 2);
+		assert!(!cache.contains(&exts[0]));
+		assert!(cache.contains(&exts[1]));
+		assert!(cache.contains(&exts[2]));
+	}
+
+	#[test]
+	fn verified_cache_skips_taken_entries_when_evicting() {
+		let sk = Alice.signing_key();
+		let exts: Vec<_> = (0..4).map(|n| signed(&sk, n, TestCall(n))).collect();
+		let mut cache = VerifiedCache::new(2);
+		cache.insert(&exts[0]);
+		cache.insert(&exts[1]);
+		assert!(cache.take(&exts[0].hash()));
+		assert!(!cache.take(&exts[0].hash()));
+		// The stale slot left by `take` must not evict the re-inserted entry.
+		cache.insert(&exts[0]);
+		cache.insert(&exts[2]);
+		assert_eq!(cache.len(), 2);
+		assert!(!cache.contains(&exts[1]));
+		assert!(cache.contains(&exts[0]));
+		assert!(cache.contains(&exts[2]));
+
+		for _ in 0..8 {
+			cache.take(&exts[3].hash());
+			cache.insert(&exts[3]);
+		}
+		assert!(cache.order.len() <= 4, "stale slots are compacted away");
+	}
+
+	#[test]
+	fn verified_cache_key_covers_signature_fields() {
+		let sk = Alice.signing_key();
+		let ext = signed(&sk, 0, TestCall(1));
+		let mut cache = VerifiedCache::new(8);
+		cache.insert(&ext);
+		let mut tampered = signed(&sk, 0, TestCall(1));
+		tampered.nonce = 1;
+		assert!(!cache.contains(&tampered));
+	}
+
+	#[test]
+	fn verify_batch_cached_skips_and_consumes_hits() {
+		let sk = Alice.signing_key();
+		let mut exts: Vec<_> = (0..3).map(|n| signed(&sk, n, TestCall(n))).collect();
+		// Cache a tampered entry: only a cache hit can make it pass.
+		exts[1].nonce = 99;
+		let cache = Mutex::new(VerifiedCache::new(8));
+		cache.lock().unwrap().insert(&exts[1]);
+
+		let results = verify_batch_cached(&exts, &cache);
+		assert!(results.iter().all(|r| r.is_ok()));
+		assert!(cache.lock().unwrap().is_empty());
+
+		// The hit was consumed, so the next import verifies it for real.
+		assert!(verify_batch_cached(&exts, &cache)[1].is_err());
+	}
+
 	#[test]
 	fn verify_batch_catches_tampered_entry() {
 		let sk = Alice.signing_key();
-		let mut exts: Vec<_> =
-			(0..3).map(|n| UncheckedExtrinsic::new_signed(&sk, n, TestCall(n))).collect();
+		let mut exts: Vec<_> = (0..3).map(|n| signed(&sk, n, TestCall(n))).collect();
 		exts[1].nonce = 99; // tamper middle entry
 		let results = verify_batch(&exts);
 		assert!(results[0].is_ok());
@@ -1,21 +1,30 @@
 use std::collections::BTreeMap;
 
-use crate::support::{KeyValueStore, kv_store};
+use crate::support::{Header, KeyValueStore, RuntimeOrigin, kv_store};
 use num::traits::{CheckedAdd, CheckedSub, One, Zero};
 use parity_scale_codec::{Decode, Encode};
 
 const PREFIX_BLOCK_NUMBER: &[u8] = b"system:block_number";
+const PREFIX_TIMESTAMP: &[u8] = b"system:timestamp";
+const PREFIX_STATE_ROOT: &[u8] = b"system:state_root";
 const PREFIX_NONCE: &[u8] = b"system:nonce:";
 
+/// The origin calls of a pallet built on `T` are dispatched with.
+pub type OriginFor<T> = RuntimeOrigin<<T as Config>::AccountId>;
+
 pub trait Config {
 	type AccountId: Ord + Clone + Encode + Decode;
 	type Nonce: Zero + CheckedAdd + Copy + One + Encode + Decode;
-	type BlockNumber: Zero + CheckedSub + CheckedAdd + Copy + One + Encode + Decode;
+	type BlockNumber: Zero + CheckedSub + CheckedAdd + Copy + One + Ord + Encode + Decode;
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Pallet<T: Config> {
 	block_number: T::BlockNumber,
+	/// `Header::timestamp` of the latest block, in milliseconds since the Unix epoch.
+	timestamp: u64,
+	/// `Header::state_root` of the latest block.
+	state_root: [u8; 32],
 	nonce: BTreeMap<T::AccountId, T::Nonce>,
 }
 
@@ -27,6 +36,14 @@ impl<T: Config> Pallet<T> {
 			.get(PREFIX_BLOCK_NUMBER)
 			.and_then(|bytes| T::BlockNumber::decode(&mut &bytes[..]).ok())
 			.unwrap_or_else(T::BlockNumber::zero);
+		let timestamp = store
+			.get(PREFIX_TIMESTAMP)
+			.and_then(|bytes| u64::decode(&mut &bytes[..]).ok())
+			.unwrap_or(0);
+		let state_root = store
+			.get(PREFIX_STATE_ROOT)
+			.and_then(|bytes| <[u8; 32]>::decode(&mut &bytes[..]).ok())
+			.unwrap_or_default();
 
 		let mut nonce = BTreeMap::new();
 		for (key, value) in store.scan_prefix(PREFIX_NONCE) {
@@ -41,13 +58,30 @@ impl<T: Config> Pallet<T> {
 			}
 		}
 
-		Self { block_number, nonce }
+		Self { block_number, timestamp, state_root, nonce }
 	}
 
 	pub fn block_number(&self) -> T::BlockNumber {
 		self.block_number
 	}
 
+	pub fn timestamp(&self) -> u64 {
+		self.timestamp
+	}
+
+	pub fn state_root(&self) -> [u8; 32] {
+		self.state_root
+	}
+
+	/// Header of the latest block, rebuilt from what it recorded.
+	pub fn header(&self) -> Header<T::BlockNumber> {
+		Header {
+			block_number: self.block_number,
+			timestamp: self.timestamp,
+			state_root: self.state_root,
+		}
+	}
+
 	pub fn nonce(&self, who: &T::AccountId) -> T::Nonce {
 		*self.nonce.get(who).unwrap_or(&T::Nonce::zero())
 	}
@@ -60,15 +94,41 @@ impl<T: Config> Pallet<T> {
 		}
 	}
 
+	pub fn set_state_root(&mut self, state_root: [u8; 32]) {
+		self.state_root = state_root;
+		if let Err(e) = kv_store().put(PREFIX_STATE_ROOT, &state_root.encode()) {
+			eprintln!("Failed to persist state root: {e}");
+		}
+	}
+
+	pub fn set_timestamp(&mut self, timestamp: u64) {
+		self.timestamp = timestamp;
+		if let Err(e) = kv_store().put(PREFIX_TIMESTAMP, &timestamp.encode()) {
+			eprintln!("Failed to persist timestamp: {e}");
+		}
+	}
+
+	/// Forget a reaped account: its nonce restarts from zero.
+	pub fn kill_account(&mut self, who: &T::AccountId) {
+		self.nonce.remove(who);
+		if let Err(e) = kv_store().delete(&Self::nonce_key(who)) {
+			eprintln!("Failed to delete nonce for account: {e}");
+		}
+	}
+
+	fn nonce_key(who: &T::AccountId) -> Vec<u8> {
+		let mut key = PREFIX_NONCE.to_vec();
+		key.extend(who.encode());
+		key
+	}
+
 	pub fn inc_nonce(&mut self, who: &T::AccountId) {
 		let user_nonce = *self.nonce.get(who).unwrap_or(&T::Nonce::zero());
 		let new_nonce = user_nonce.checked_add(&T::Nonce::one()).unwrap();
 		self.nonce.insert(who.clone(), new_nonce);
 
-		let mut key = PREFIX_NONCE.to_vec();
-		key.extend(who.encode());
 		let encoded = new_nonce.encode();
-		if let Err(e) = kv_store().put(&key, &encoded) {
+		if let Err(e) = kv_store().put(&Self::nonce_key(who), &encoded) {
 			eprintln!("Failed to persist nonce for account: {e}");
 		}
 	}
@@ -103,6 +163,25 @@ mod tests {
 		assert_eq!(s.block_number(), 2);
 	}
 
+	#[test]
+	fn set_timestamp_is_persisted() {
+		let mut s = new();
+		assert_eq!(s.timestamp(), 0);
+		s.set_timestamp(1_700_000_000_000);
+		assert_eq!(s.timestamp(), 1_700_000_000_000);
+		assert_eq!(new().timestamp(), 1_700_000_000_000);
+	}
+
+	#[test]
+	fn header_reports_the_recorded_state_root() {
+		let mut s = new();
+		s.inc_block_number();
+		s.set_timestamp(5);
+		s.set_state_root([7; 32]);
+		let header = new().header();
+		assert_eq!((header.block_number, header.timestamp, header.state_root), (1, 5, [7; 32]));
+	}
+
 	#[test]
 	fn nonce_starts_at_zero_for_unknown_account() {
 		assert_eq!(new().nonce(&"alice".to_string()), 0);
@@ -141,4 +220,15 @@ mod tests {
 		assert_eq!(s.nonce(&"bob".to_string()), 1);
 		assert_eq!(s.nonce(&"charlie".to_string()), 0);
 	}
+
+	#[test]
+	fn kill_account_resets_nonce() {
+		let mut s = new();
+		s.inc_nonce(&"alice".to_string());
+		s.inc_nonce(&"bob".to_string());
+		s.kill_account(&"alice".to_string());
+		assert_eq!(s.nonce(&"alice".to_string()), 0);
+		assert_eq!(s.nonce(&"bob".to_string()), 1);
+		assert_eq!(new().nonce(&"alice".to_string()), 0);
+	}
 }
@@ -0,0 +1,329 @@
+use num::traits::{CheckedAdd, CheckedMul, CheckedSub, Zero};
+use parity_scale_codec::{Decode, Encode};
+use std::collections::BTreeMap;
+
+use crate::{
+	support::{
+		Currency, DispatchResult, ExistenceRequirement, KeyValueStore, LockIdentifier,
+		ensure_signed, kv_store, load_prefix,
+	},
+	system::{self, OriginFor},
+};
+
+const PREFIX_VESTING: &[u8] = b"vesting:";
+/// Name of the `balances` lock holding the still-vesting funds.
+pub const VESTING_ID: LockIdentifier = *b"vesting ";
+
+/// Linear release of `locked` funds: nothing before `starting_block`, then `per_block` more each
+/// block until everything is free.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct VestingInfo<Balance, BlockNumber> {
+	pub locked: Balance,
+	pub per_block: Balance,
+	pub starting_block: BlockNumber,
+}
+
+impl<Balance, BlockNumber> VestingInfo<Balance, BlockNumber>
+where
+	Balance: Zero + CheckedSub + CheckedMul + Copy + From<BlockNumber>,
+	BlockNumber: CheckedSub + Copy + Ord,
+{
+	/// Amount still locked at block `now`.
+	pub fn locked_at(&self, now: BlockNumber) -> Balance {
+		if now <= self.starting_block {
+			return self.locked;
+		}
+		let elapsed = Balance::from(now.checked_sub(&self.starting_block).expect("now is later"));
+		elapsed
+			.checked_mul(&self.per_block)
+			.and_then(|vested| self.locked.checked_sub(&vested))
+			.unwrap_or_else(Balance::zero)
+	}
+}
+
+pub trait Config: system::Config {
+	type Balance: Zero
+		+ CheckedAdd
+		+ CheckedSub
+		+ CheckedMul
+		+ Copy
+		+ Ord
+		+ From<Self::BlockNumber>
+		+ Encode
+		+ Decode;
+	/// Where vesting funds come from and are locked; `balances::Pallet` in the runtime.
+	type Currency: Currency<Self::AccountId, Balance = Self::Balance, BlockNumber = Self::BlockNumber>;
+	/// Smallest amount `vested_transfer` will lock up.
+	const MIN_VESTED_TRANSFER: Self::Balance;
+	/// Most schedules one account can have running at once.
+	const MAX_VESTING_SCHEDULES: usize;
+}
+
+type VestingInfoOf<T> = VestingInfo<<T as Config>::Balance, <T as system::Config>::BlockNumber>;
+type Schedules<Balance, BlockNumber> = Vec<VestingInfo<Balance, BlockNumber>>;
+
+#[derive(Clone, Debug)]
+pub struct Pallet<T: Config> {
+	schedules: BTreeMap<T::AccountId, Schedules<T::Balance, T::BlockNumber>>,
+	/// Current block, set by `on_initialize`.
+	now: T::BlockNumber,
+}
+
+impl<T: Config> Pallet<T> {
+	pub fn new() -> Self {
+		Self { schedules: load_prefix(PREFIX_VESTING), now: T::BlockNumber::zero() }
+	}
+
+	fn schedules_key(who: &T::AccountId) -> Vec<u8> {
+		let mut key = PREFIX_VESTING.to_vec();
+		key.extend(who.encode());
+		key
+	}
+
+	/// Called by the runtime at the start of every block.
+	pub fn on_initialize(&mut self, now: T::BlockNumber) {
+		self.now = now;
+	}
+
+	pub fn schedules(&self, who: &T::AccountId) -> &[VestingInfoOf<T>] {
+		self.schedules.get(who).map_or(&[], Vec::as_slice)
+	}
+
+	/// Total still locked across `who`'s schedules at the current block.
+	pub fn vesting_balance(&self, who: &T::AccountId) -> T::Balance {
+		self.schedules(who)
+			.iter()
+			.map(|schedule| schedule.locked_at(self.now))
+			.try_fold(T::Balance::zero(), |sum, locked| sum.checked_add(&locked))
+			.expect("locked funds are bounded by total issuance")
+	}
+
+	/// Drop finished schedules and set `who`'s vesting lock to what is still locked.
+	fn update_lock(&mut self, currency: &mut T::Currency, who: &T::AccountId) {
+		let mut schedules = self.schedules.remove(who).unwrap_or_default();
+		schedules.retain(|schedule| !schedule.locked_at(self.now).is_zero());
+		let key = Self::schedules_key(who);
+
+		if schedules.is_empty() {
+			currency.remove_lock(VESTING_ID, who);
+			if let Err(e) = kv_store().delete(&key) {
+				eprintln!("Failed to delete vesting schedules: {e}");
+			}
+			return;
+		}
+
+		if let Err(e) = kv_store().put(&key, &schedules.encode()) {
+			eprintln!("Failed to persist vesting schedules: {e}");
+		}
+		self.schedules.insert(who.clone(), schedules);
+		currency.set_lock(VESTING_ID, who, self.vesting_balance(who), None);
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Unlock whatever the caller's schedules have released so far.
+	pub fn vest(&mut self, origin: OriginFor<T>, currency: &mut T::Currency) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if !self.schedules.contains_key(&caller) {
+			return Err("Account has no vesting schedule.");
+		}
+		self.update_lock(currency, &caller);
+		Ok(())
+	}
+
+	/// Transfer `schedule.locked` to `target` and lock it there under `schedule`.
+	pub fn vested_transfer(
+		&mut self,
+		origin: OriginFor<T>,
+		currency: &mut T::Currency,
+		target: T::AccountId,
+		schedule: VestingInfo<T::Balance, T::BlockNumber>,
+	) -> DispatchResult {
+		let caller = ensure_signed(origin)?;
+		if schedule.locked < T::MIN_VESTED_TRANSFER {
+			return Err("Vested amount is below the minimum vested transfer.");
+		}
+		if schedule.per_block.is_zero() {
+			return Err("Vesting schedule never unlocks anything.");
+		}
+		if self.schedules(&target).len() >= T::MAX_VESTING_SCHEDULES {
+			return Err("Target has too many vesting schedules.");
+		}
+
+		currency.transfer(&caller, &target, schedule.locked, ExistenceRequirement::AllowDeath)?;
+		self.schedules.entry(target.clone()).or_default().push(schedule);
+		self.update_lock(currency, &target);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::balances;
+	use crate::support::RuntimeOrigin;
+
+	struct TestConfig;
+	impl system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+	}
+	impl balances::Config for TestConfig {
+		type Balance = u128;
+		const EXISTENTIAL_DEPOSIT: u128 = 10;
+	}
+	impl Config for TestConfig {
+		type Balance = u128;
+		type Currency = balances::Pallet<TestConfig>;
+		const MIN_VESTED_TRANSFER: u128 = 100;
+		const MAX_VESTING_SCHEDULES: usize = 2;
+	}
+
+	fn schedule(locked: u128, per_block: u128, starting_block: u32) -> VestingInfo<u128, u32> {
+		VestingInfo { locked, per_block, starting_block }
+	}
+
+	/// Fresh pallets at block `now`, with Alice holding 10_000.
+	fn setup(now: u32) -> (Pallet<TestConfig>, balances::Pallet<TestConfig>) {
+		let (mut vesting, mut balances) = (Pallet::new(), balances::Pallet::new());
+		vesting.on_initialize(now);
+		balances.on_initialize(now);
+		balances.set_balance(&"alice".to_string(), 10_000);
+		(vesting, balances)
+	}
+
+	#[test]
+	fn locked_at_releases_linearly() {
+		let s = schedule(1_000, 100, 10);
+		assert_eq!(s.locked_at(0), 1_000);
+		assert_eq!(s.locked_at(10), 1_000);
+		assert_eq!(s.locked_at(13), 700);
+		assert_eq!(s.locked_at(20), 0);
+		assert_eq!(s.locked_at(u32::MAX), 0);
+	}
+
+	#[test]
+	fn vested_transfer_locks_funds_at_the_target() {
+		let (mut vesting, mut balances) = setup(1);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let call_schedule = schedule(1_000, 100, 5);
+		vesting
+			.vested_transfer(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				bob.clone(),
+				call_schedule,
+			)
+			.unwrap();
+
+		assert_eq!(balances.balance(&bob), 1_000);
+		assert_eq!(balances.usable_balance(&bob), 0);
+		assert_eq!(vesting.schedules(&bob), [call_schedule]);
+		assert_eq!(
+			balances.transfer(RuntimeOrigin::Signed(bob.clone()), alice, 1),
+			Err("Balance is locked."),
+			"locked part cannot leave through balances"
+		);
+	}
+
+	#[test]
+	fn vest_unlocks_released_funds_and_clears_finished_schedules() {
+		let (mut vesting, mut balances) = setup(1);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		vesting
+			.vested_transfer(
+				RuntimeOrigin::Signed(alice),
+				&mut balances,
+				bob.clone(),
+				schedule(1_000, 100, 5),
+			)
+			.unwrap();
+
+		vesting.on_initialize(8);
+		balances.on_initialize(8);
+		assert_eq!(balances.usable_balance(&bob), 0, "nothing unlocks until `vest`");
+		vesting.vest(RuntimeOrigin::Signed(bob.clone()), &mut balances).unwrap();
+		assert_eq!(balances.usable_balance(&bob), 300);
+
+		vesting.on_initialize(15);
+		vesting.vest(RuntimeOrigin::Signed(bob.clone()), &mut balances).unwrap();
+		assert_eq!(balances.usable_balance(&bob), 1_000);
+		assert!(vesting.schedules(&bob).is_empty());
+		assert!(balances.locks(&bob).is_empty());
+		assert_eq!(
+			vesting.vest(RuntimeOrigin::Signed(bob), &mut balances),
+			Err("Account has no vesting schedule.")
+		);
+	}
+
+	#[test]
+	fn schedules_add_up_and_are_capped() {
+		let (mut vesting, mut balances) = setup(1);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		vesting
+			.vested_transfer(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				bob.clone(),
+				schedule(500, 50, 1),
+			)
+			.unwrap();
+		vesting
+			.vested_transfer(
+				RuntimeOrigin::Signed(alice.clone()),
+				&mut balances,
+				bob.clone(),
+				schedule(200, 10, 1),
+			)
+			.unwrap();
+		assert_eq!(balances.frozen_balance(&bob), 700);
+		assert_eq!(
+			vesting.vested_transfer(
+				RuntimeOrigin::Signed(alice),
+				&mut balances,
+				bob.clone(),
+				schedule(100, 1, 1)
+			),
+			Err("Target has too many vesting schedules.")
+		);
+		assert_eq!(Pallet::<TestConfig>::new().schedules(&bob).len(), 2, "persisted");
+	}
+
+	#[test]
+	fn vested_transfer_rejects_bad_schedules() {
+		let (mut vesting, mut balances) = setup(1);
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		assert!(
+			vesting
+				.vested_transfer(
+					RuntimeOrigin::Signed(alice.clone()),
+					&mut balances,
+					bob.clone(),
+					schedule(99, 1, 1)
+				)
+				.is_err()
+		);
+		assert!(
+			vesting
+				.vested_transfer(
+					RuntimeOrigin::Signed(alice.clone()),
+					&mut balances,
+					bob.clone(),
+					schedule(500, 0, 1)
+				)
+				.is_err()
+		);
+		assert_eq!(
+			vesting.vested_transfer(
+				RuntimeOrigin::Signed(alice),
+				&mut balances,
+				bob.clone(),
+				schedule(20_000, 1, 1)
+			),
+			Err("Not enough funds.")
+		);
+		assert!(vesting.schedules(&bob).is_empty());
+	}
+}
@@ -1,5 +1,5 @@
 use parity_scale_codec::{Decode, Encode};
-use rust_state_machine::{balances, proof_of_existence, support, types, RuntimeCall};
+use rust_state_machine::{balances, proof_of_existence, sudo, support, types, RuntimeCall};
 use support::keyring::AccountKeyring::{Alice, Bob};
 
 // ---------------------------------------------------------------------------
@@ -9,14 +9,16 @@ use support::keyring::AccountKeyring::{Alice, Bob};
 fn transfer_ext(nonce: u32) -> types::Extrinsic {
 	let call =
 		RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 100 });
-	support::UncheckedExtrinsic::new_signed(&Alice.signing_key(), nonce, call)
+	support::UncheckedExtrinsic::new_signed(&Alice.signing_key(), nonce, u64::MAX, call)
 }
 
 fn claim_ext(nonce: u32) -> types::Extrinsic {
 	let call = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-		claim: "test-document".to_string(),
+		claim: support::blake2_256(b"test-document"),
+		metadata: String::new(),
+		expires_at: None,
 	});
-	support::UncheckedExtrinsic::new_signed(&Alice.signing_key(), nonce, call)
+	support::UncheckedExtrinsic::new_signed(&Alice.signing_key(), nonce, u64::MAX, call)
 }
 
 // ---------------------------------------------------------------------------
@@ -26,13 +28,19 @@ fn claim_ext(nonce: u32) -> types::Extrinsic {
 #[test]
 fn block_with_extrinsics_roundtrip() {
 	let block = types::Block {
-		header: support::Header { block_number: 42 },
+		header: support::Header {
+			block_number: 42,
+			timestamp: 1_700_000_000_000,
+			state_root: [7; 32],
+		},
 		extrinsics: vec![transfer_ext(0), claim_ext(1)],
 	};
 
 	let decoded = types::Block::decode(&mut &block.encode()[..]).expect("decode succeeds");
 
 	assert_eq!(decoded.header.block_number, 42);
+	assert_eq!(decoded.header.timestamp, 1_700_000_000_000);
+	assert_eq!(decoded.header.state_root, [7; 32]);
 	assert_eq!(decoded.extrinsics.len(), 2);
 	assert_eq!(decoded.extrinsics[0].signer, Alice.public());
 	assert_eq!(decoded.extrinsics[0].nonce, 0);
@@ -41,8 +49,8 @@ fn block_with_extrinsics_roundtrip() {
 
 #[test]
 fn empty_block_roundtrip() {
-	let block =
-		types::Block { header: support::Header { block_number: 1 }, extrinsics: vec![] };
+	let header = support::Header { block_number: 1, timestamp: 0, state_root: [0; 32] };
+	let block = types::Block { header, extrinsics: vec![] };
 	let decoded = types::Block::decode(&mut &block.encode()[..]).unwrap();
 	assert_eq!(decoded.header.block_number, 1);
 	assert!(decoded.extrinsics.is_empty());
@@ -58,6 +66,13 @@ fn extrinsic_fields_survive_encode_decode() {
 	assert_eq!(decoded.signature, ext.signature);
 }
 
+#[test]
+fn hash_survives_encode_decode_roundtrip() {
+	let ext = transfer_ext(5);
+	let decoded = types::Extrinsic::decode(&mut &ext.encode()[..]).unwrap();
+	assert_eq!(decoded.hash(), ext.hash());
+}
+
 #[test]
 fn encoded_bytes_are_deterministic() {
 	assert_eq!(transfer_ext(3).encode(), transfer_ext(3).encode());
@@ -108,11 +123,13 @@ fn different_signers_produce_different_signatures() {
 	let ext_alice = support::UncheckedExtrinsic::new_signed(
 		&Alice.signing_key(),
 		0,
+		u64::MAX,
 		RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 50 }),
 	);
 	let ext_bob = support::UncheckedExtrinsic::new_signed(
 		&Bob.signing_key(),
 		0,
+		u64::MAX,
 		RuntimeCall::balances(balances::Call::transfer { to: Alice.public(), amount: 50 }),
 	);
 	assert_ne!(ext_alice.signature, ext_bob.signature);
@@ -146,3 +163,18 @@ fn verify_batch_on_empty_slice_returns_empty_vec() {
 	let exts: Vec<types::Extrinsic> = vec![];
 	assert!(support::verify_batch(&exts).is_empty());
 }
+
+#[test]
+fn calls_nested_past_the_depth_limit_do_not_decode() {
+	use parity_scale_codec::DecodeLimit;
+	let nest = |depth: u32| {
+		let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 1 });
+		(0..depth)
+			.fold(call, |call, _| RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(call) }))
+	};
+	let limit = support::MAX_EXTRINSIC_DEPTH;
+	let shallow = nest(3).encode();
+	assert!(RuntimeCall::decode_with_depth_limit(limit, &mut &shallow[..]).is_ok());
+	let deep = nest(limit + 1).encode();
+	assert!(RuntimeCall::decode_with_depth_limit(limit, &mut &deep[..]).is_err());
+}
@@ -1,7 +1,10 @@
 use rust_state_machine::{
-	maybe_apply_genesis, proof_of_existence, support, types, balances, Runtime, RuntimeCall,
+	assets, maybe_apply_genesis, nfts, proof_of_existence, sudo, support, types, balances, vesting,
+	Runtime, RuntimeCall,
 };
 use support::keyring::AccountKeyring::{Alice, Bob, Charlie};
+use support::{Dispatch, RuntimeOrigin};
+use parity_scale_codec::{Decode, Encode};
 use std::sync::OnceLock;
 use tempfile::TempDir;
 
@@ -29,39 +32,69 @@ fn signed_transfer(
 	amount: types::Balance,
 ) -> types::Extrinsic {
 	let call = RuntimeCall::balances(balances::Call::transfer { to: to.public(), amount });
-	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, call)
+	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, valid_until(), call)
 }
 
-fn signed_claim(
-	from: support::keyring::AccountKeyring,
-	nonce: u32,
-	claim: &str,
-) -> types::Extrinsic {
-	let call = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-		claim: claim.to_string(),
-	});
-	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, call)
+/// A dev account for the PoE tests only, so the deposits its claims reserve never show up in
+/// the balance tests sharing this binary's database.
+fn claimer(name: &str) -> support::Pair {
+	let uri = support::keyring::from_name(&format!("//Poe{name}")).unwrap();
+	uri.pair(support::CryptoScheme::Ed25519).unwrap()
 }
 
-fn signed_revoke(
-	from: support::keyring::AccountKeyring,
+/// `claimer`'s account, funded with 1_000 for claim deposits.
+fn funded_claimer(rt: &mut Runtime, name: &str) -> (support::Pair, types::AccountId) {
+	let pair = claimer(name);
+	let account = pair.public().into_account();
+	rt.balances.set_balance(&account, 1_000);
+	(pair, account)
+}
+
+fn signed_poe_call(
+	from: &support::Pair,
 	nonce: u32,
-	claim: &str,
+	call: proof_of_existence::Call<Runtime>,
 ) -> types::Extrinsic {
-	let call = RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-		claim: claim.to_string(),
-	});
-	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, call)
+	let call = RuntimeCall::proof_of_existence(call);
+	support::UncheckedExtrinsic::new_signed(from, nonce, valid_until(), call)
 }
 
-/// Build the next valid block for this runtime using its current in-memory block number.
+fn signed_claim(from: &support::Pair, nonce: u32, claim: &str) -> types::Extrinsic {
+	let claim = support::blake2_256(claim.as_bytes());
+	let call =
+		proof_of_existence::Call::create_claim { claim, metadata: String::new(), expires_at: None };
+	signed_poe_call(from, nonce, call)
+}
+
+fn signed_revoke(from: &support::Pair, nonce: u32, claim: &str) -> types::Extrinsic {
+	let claim = support::blake2_256(claim.as_bytes());
+	signed_poe_call(from, nonce, proof_of_existence::Call::revoke_claim { claim })
+}
+
+/// Build the next valid block for this runtime using its current in-memory block number and the
+/// wall clock, which never runs behind a timestamp persisted earlier in the test run. Its state
+/// root is left for `author_block` to fill in.
 fn next_block(rt: &Runtime, exts: Vec<types::Extrinsic>) -> types::Block {
 	types::Block {
-		header: support::Header { block_number: rt.system.block_number() + 1 },
+		header: support::Header {
+			block_number: rt.system.block_number() + 1,
+			timestamp: support::now_millis(),
+			state_root: [0; 32],
+		},
 		extrinsics: exts,
 	}
 }
 
+/// `valid_until` for the extrinsics a test signs: the default lifetime from now.
+fn valid_until() -> u64 {
+	support::now_millis() + support::EXTRINSIC_LIFETIME_MILLIS
+}
+
+/// Claims are made on the `blake2_256` digest of the document; tests name documents by text.
+fn claim_owner(rt: &Runtime, claim: &str) -> Option<types::AccountId> {
+	rt.proof_of_existence.get_claim(&support::blake2_256(claim.as_bytes())).map(|info| info.owner)
+}
+
 // ---------------------------------------------------------------------------
 // Block number / system pallet
 // ---------------------------------------------------------------------------
@@ -71,7 +104,7 @@ fn execute_block_increments_block_number() {
 	init();
 	let mut rt = Runtime::new();
 	let before = rt.system.block_number();
-	rt.execute_block(next_block(&rt, vec![])).unwrap();
+	rt.author_block(next_block(&rt, vec![])).unwrap();
 	assert_eq!(rt.system.block_number(), before + 1);
 }
 
@@ -80,10 +113,95 @@ fn execute_block_rejects_wrong_header_number() {
 	init();
 	let mut rt = Runtime::new();
 	let bad = types::Block {
-		header: support::Header { block_number: rt.system.block_number() + 5 },
+		header: support::Header {
+			block_number: rt.system.block_number() + 5,
+			timestamp: support::now_millis(),
+			state_root: [0; 32],
+		},
 		extrinsics: vec![],
 	};
+	let block_number = rt.system.block_number();
 	assert!(rt.execute_block(bad).is_err());
+	assert_eq!(rt.system.block_number(), block_number);
+}
+
+#[test]
+fn execute_block_checks_the_state_root_author_block_fills_in() {
+	init();
+	let mut rt = Runtime::new();
+	let sealed = rt.author_block(next_block(&rt, vec![])).unwrap();
+	assert_eq!(sealed.header.state_root, rt.system.state_root());
+
+	let mut forged = next_block(&rt, vec![]);
+	forged.header.state_root = [0xff; 32];
+	assert_eq!(
+		rt.execute_block(forged),
+		Err("block state root does not match the resulting state")
+	);
+}
+
+#[test]
+fn a_block_with_a_bad_state_root_leaves_state_untouched() {
+	init();
+	let mut rt = Runtime::new();
+	let (kate, account) = funded_claimer(&mut rt, "Kate");
+	let nonce = rt.system.nonce(&account);
+	let block_number = rt.system.block_number();
+	let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 300 });
+	let transfer = support::UncheckedExtrinsic::new_signed(&kate, nonce, valid_until(), call);
+	let hash = transfer.hash();
+
+	let mut forged = next_block(&rt, vec![transfer]);
+	forged.header.state_root = [0xff; 32];
+	assert!(rt.execute_block(forged).is_err());
+
+	for rt in [&rt, &Runtime::new()] {
+		assert_eq!(rt.system.block_number(), block_number);
+		assert_eq!(rt.balances.balance(&account), 1_000);
+		assert_eq!(rt.system.nonce(&account), nonce);
+	}
+	assert!(support::extrinsic_record::<types::BlockNumber>(&hash).is_none());
+}
+
+#[test]
+fn a_rejected_block_leaves_the_runtime_exactly_as_it_was() {
+	init();
+	let mut rt = Runtime::new();
+	let (peggy, account) = funded_claimer(&mut rt, "Peggy");
+	// Sets every pallet's current block and builds the claims tree, none of which is stored.
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+	let before = format!("{rt:?}");
+
+	let nonce = rt.system.nonce(&account);
+	let mut forged = next_block(&rt, vec![signed_claim(&peggy, nonce, "rollback doc")]);
+	forged.header.state_root = [0xff; 32];
+	assert!(rt.execute_block(forged).is_err());
+
+	assert_eq!(format!("{rt:?}"), before);
+	assert_eq!(claim_owner(&rt, "rollback doc"), None);
+}
+
+#[test]
+fn execute_block_records_timestamp_and_rejects_going_back() {
+	init();
+	let mut rt = Runtime::new();
+	let block = next_block(&rt, vec![]);
+	let timestamp = block.header.timestamp;
+	rt.author_block(block).unwrap();
+	assert_eq!(rt.system.timestamp(), timestamp);
+
+	let mut earlier = next_block(&rt, vec![]);
+	earlier.header.timestamp = timestamp - 1;
+	assert_eq!(rt.execute_block(earlier), Err("block timestamp is earlier than its parent's"));
+}
+
+#[test]
+fn execute_block_rejects_timestamps_too_far_ahead() {
+	init();
+	let mut rt = Runtime::new();
+	let mut ahead = next_block(&rt, vec![]);
+	ahead.header.timestamp = support::now_millis() + support::MAX_TIMESTAMP_DRIFT_MILLIS + 60_000;
+	assert_eq!(rt.execute_block(ahead), Err("block timestamp is too far in the future"));
 }
 
 #[test]
@@ -92,7 +210,7 @@ fn multiple_empty_blocks_advance_block_number() {
 	let mut rt = Runtime::new();
 	let start = rt.system.block_number();
 	for _ in 0..3 {
-		rt.execute_block(next_block(&rt, vec![])).unwrap();
+		rt.author_block(next_block(&rt, vec![])).unwrap();
 	}
 	assert_eq!(rt.system.block_number(), start + 3);
 }
@@ -109,7 +227,7 @@ fn single_transfer_updates_balances() {
 	rt.balances.set_balance(&Bob.public(), 0);
 	let nonce = rt.system.nonce(&Alice.public());
 
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 300)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 300)])).unwrap();
 
 	assert_eq!(rt.balances.balance(&Alice.public()), 700);
 	assert_eq!(rt.balances.balance(&Bob.public()), 300);
@@ -123,7 +241,7 @@ fn transfer_exact_balance_drains_sender() {
 	rt.balances.set_balance(&Bob.public(), 0);
 	let nonce = rt.system.nonce(&Alice.public());
 
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 500)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 500)])).unwrap();
 
 	assert_eq!(rt.balances.balance(&Alice.public()), 0);
 	assert_eq!(rt.balances.balance(&Bob.public()), 500);
@@ -139,7 +257,7 @@ fn insufficient_balance_fails_dispatch_block_still_commits() {
 	let before = rt.system.block_number();
 
 	// Block itself succeeds even though the dispatch fails inside.
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 9_999)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, nonce, Bob, 9_999)])).unwrap();
 
 	assert_eq!(rt.system.block_number(), before + 1);
 	assert_eq!(rt.balances.balance(&Alice.public()), 50);
@@ -156,7 +274,7 @@ fn two_transfers_in_one_block_from_different_senders() {
 	let an = rt.system.nonce(&Alice.public());
 	let bn = rt.system.nonce(&Bob.public());
 
-	rt.execute_block(next_block(&rt, vec![
+	rt.author_block(next_block(&rt, vec![
 		signed_transfer(Alice, an, Charlie, 100),
 		signed_transfer(Bob, bn, Charlie, 200),
 	]))
@@ -167,6 +285,357 @@ fn two_transfers_in_one_block_from_different_senders() {
 	assert_eq!(rt.balances.balance(&Charlie.public()), 300);
 }
 
+#[test]
+fn draining_an_account_reaps_it_and_resets_its_nonce() {
+	init();
+	let mut rt = Runtime::new();
+	let dave = support::keyring::from_name("//Dave").unwrap();
+	let dave = dave.pair(support::CryptoScheme::Ed25519).unwrap();
+	let dave_id = dave.public().into_account();
+	rt.balances.set_balance(&dave_id, 105);
+	let issuance = rt.balances.total_issuance();
+
+	// Leaves 5, below the existential deposit: Dave is reaped and the dust burned.
+	let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 100 });
+	let ext = support::UncheckedExtrinsic::new_signed(&dave, 0, valid_until(), call);
+	let (hash, encoded) = (ext.hash(), ext.encode());
+	rt.author_block(next_block(&rt, vec![ext])).unwrap();
+
+	assert_eq!(rt.balances.balance(&dave_id), 0);
+	assert_eq!(rt.system.nonce(&dave_id), 0);
+	assert_eq!(rt.balances.total_issuance(), issuance - 5);
+	assert_eq!(Runtime::new().system.nonce(&dave_id), 0);
+
+	// Funded again, Dave's nonce is back at 0, yet his old transfer cannot be replayed.
+	rt.balances.set_balance(&dave_id, 105);
+	let replay = types::Extrinsic::decode(&mut &encoded[..]).unwrap();
+	rt.author_block(next_block(&rt, vec![replay])).unwrap();
+	assert_eq!(rt.balances.balance(&dave_id), 105);
+	assert_eq!(rt.system.nonce(&dave_id), 0);
+	let record = support::extrinsic_record::<types::BlockNumber>(&hash).expect("indexed");
+	assert_eq!(record.outcome, support::ExtrinsicOutcome::Applied, "the original record stays");
+}
+
+#[test]
+fn expired_extrinsics_are_skipped() {
+	init();
+	let mut rt = Runtime::new();
+	let (rupert, account) = funded_claimer(&mut rt, "Rupert");
+	let nonce = rt.system.nonce(&account);
+	let block = next_block(&rt, vec![]);
+	let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 10 });
+	let until = block.header.timestamp - 1;
+	let stale = support::UncheckedExtrinsic::new_signed(&rupert, nonce, until, call);
+	let hash = stale.hash();
+	rt.author_block(types::Block { extrinsics: vec![stale], ..block }).unwrap();
+
+	assert_eq!(rt.balances.balance(&account), 1_000);
+	assert_eq!(rt.system.nonce(&account), nonce);
+	let record = support::extrinsic_record::<types::BlockNumber>(&hash).expect("indexed");
+	assert_eq!(record.outcome, support::ExtrinsicOutcome::Expired);
+}
+
+fn signed_balances_call(
+	from: support::keyring::AccountKeyring,
+	nonce: u32,
+	call: balances::Call<Runtime>,
+) -> types::Extrinsic {
+	let call = RuntimeCall::balances(call);
+	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, valid_until(), call)
+}
+
+#[test]
+fn transfer_keep_alive_refuses_to_reap_sender() {
+	init();
+	let mut rt = Runtime::new();
+	rt.balances.set_balance(&Alice.public(), 100);
+	rt.balances.set_balance(&Bob.public(), 0);
+	let nonce = rt.system.nonce(&Alice.public());
+
+	rt.author_block(next_block(&rt, vec![
+		signed_balances_call(Alice, nonce, balances::Call::transfer_keep_alive {
+			to: Bob.public(),
+			amount: 95,
+		}),
+		signed_balances_call(Alice, nonce + 1, balances::Call::transfer_keep_alive {
+			to: Bob.public(),
+			amount: 90,
+		}),
+	]))
+	.unwrap();
+
+	assert_eq!(rt.balances.balance(&Alice.public()), 10);
+	assert_eq!(rt.balances.balance(&Bob.public()), 90);
+}
+
+#[test]
+fn transfer_all_moves_everything_withdrawable() {
+	init();
+	let mut rt = Runtime::new();
+	rt.balances.set_balance(&Alice.public(), 100);
+	rt.balances.set_balance(&Bob.public(), 100);
+	rt.balances.set_balance(&Charlie.public(), 0);
+	let an = rt.system.nonce(&Alice.public());
+	let bn = rt.system.nonce(&Bob.public());
+
+	rt.author_block(next_block(&rt, vec![
+		signed_balances_call(Alice, an, balances::Call::transfer_all {
+			to: Charlie.public(),
+			keep_alive: true,
+		}),
+		signed_balances_call(Bob, bn, balances::Call::transfer_all {
+			to: Charlie.public(),
+			keep_alive: false,
+		}),
+	]))
+	.unwrap();
+
+	assert_eq!(rt.balances.balance(&Alice.public()), 10, "existential deposit kept");
+	assert_eq!(rt.balances.balance(&Bob.public()), 0);
+	assert_eq!(rt.system.nonce(&Bob.public()), 0, "Bob was reaped");
+	assert_eq!(rt.balances.balance(&Charlie.public()), 190);
+}
+
+#[test]
+fn force_calls_are_root_only() {
+	init();
+	let mut rt = Runtime::new();
+	rt.balances.set_balance(&Alice.public(), 1_000);
+	rt.balances.set_balance(&Bob.public(), 500);
+	rt.balances.set_balance(&Charlie.public(), 0);
+	let an = rt.system.nonce(&Alice.public());
+
+	// Extrinsics are dispatched as signed, so even Alice's force calls fail.
+	rt.author_block(next_block(&rt, vec![
+		signed_balances_call(Alice, an, balances::Call::force_transfer {
+			source: Bob.public(),
+			to: Alice.public(),
+			amount: 500,
+		}),
+		signed_balances_call(Alice, an + 1, balances::Call::force_set_balance {
+			who: Alice.public(),
+			new_free: 1_000_000,
+		}),
+	]))
+	.unwrap();
+	assert_eq!(rt.balances.balance(&Alice.public()), 1_000);
+	assert_eq!(rt.balances.balance(&Bob.public()), 500);
+
+	// Dispatched as root, they move Bob's funds and set Charlie's balance.
+	let issuance = rt.balances.total_issuance();
+	let force_transfer = balances::Call::force_transfer {
+		source: Bob.public(),
+		to: Charlie.public(),
+		amount: 200,
+	};
+	rt.dispatch(RuntimeOrigin::Root, RuntimeCall::balances(force_transfer)).unwrap();
+	let set_balance = balances::Call::force_set_balance { who: Charlie.public(), new_free: 50 };
+	rt.dispatch(RuntimeOrigin::Root, RuntimeCall::balances(set_balance)).unwrap();
+	assert_eq!(rt.balances.balance(&Bob.public()), 300);
+	assert_eq!(rt.balances.balance(&Charlie.public()), 50);
+	assert_eq!(rt.balances.total_issuance(), issuance - 150);
+}
+
+#[test]
+fn force_set_balance_below_existential_deposit_reaps() {
+	init();
+	let mut rt = Runtime::new();
+	// Tests share the database, so this one installs a sudo key of its own.
+	let (key, key_account) = funded_claimer(&mut rt, "Mike");
+	rt.sudo.initialize_key(key_account);
+	let (leo, leo_account) = funded_claimer(&mut rt, "Leo");
+	let ln = rt.system.nonce(&leo_account);
+	let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 500 });
+	let ext = support::UncheckedExtrinsic::new_signed(&leo, ln, valid_until(), call);
+	rt.author_block(next_block(&rt, vec![ext])).unwrap();
+	let issuance = rt.balances.total_issuance();
+	let kn = rt.system.nonce(&key_account);
+
+	// Applied in a block, so the runtime's after-dispatch handling runs as it would on-chain.
+	let call = balances::Call::force_set_balance { who: leo_account, new_free: 5 };
+	rt.author_block(next_block(&rt, vec![signed_sudo(&key, kn, RuntimeCall::balances(call))]))
+		.unwrap();
+
+	assert_eq!(rt.balances.balance(&leo_account), 0);
+	assert_eq!(rt.balances.total_issuance(), issuance - 500);
+	for rt in [&rt, &Runtime::new()] {
+		assert_eq!(rt.system.nonce(&leo_account), 0, "reaping resets the nonce");
+	}
+}
+
+// ---------------------------------------------------------------------------
+// Vesting
+// ---------------------------------------------------------------------------
+
+#[test]
+fn vested_funds_unlock_block_by_block() {
+	init();
+	let mut rt = Runtime::new();
+	let eve = support::keyring::from_name("//Eve").unwrap();
+	let eve = eve.pair(support::CryptoScheme::Ed25519).unwrap();
+	let eve_id = eve.public().into_account();
+	rt.balances.set_balance(&Alice.public(), 10_000);
+	let nonce = rt.system.nonce(&Alice.public());
+	let start = rt.system.block_number() + 1;
+
+	let schedule = vesting::VestingInfo { locked: 1_000, per_block: 100, starting_block: start };
+	let call = vesting::Call::vested_transfer { target: eve_id, schedule };
+	let ext = support::UncheckedExtrinsic::new_signed(
+		&Alice.signing_key(),
+		nonce,
+		valid_until(),
+		RuntimeCall::vesting(call),
+	);
+	rt.author_block(next_block(&rt, vec![ext])).unwrap();
+	assert_eq!(rt.balances.balance(&eve_id), 1_000);
+	assert_eq!(rt.balances.usable_balance(&eve_id), 0);
+
+	// Three blocks later, `vest` releases 300; the rest still cannot be transferred.
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+	let vest = RuntimeCall::vesting(vesting::Call::vest {});
+	let transfer = |amount| {
+		RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount })
+	};
+	rt.author_block(next_block(&rt, vec![
+		support::UncheckedExtrinsic::new_signed(&eve, 0, valid_until(), vest),
+		support::UncheckedExtrinsic::new_signed(&eve, 1, valid_until(), transfer(301)),
+		support::UncheckedExtrinsic::new_signed(&eve, 2, valid_until(), transfer(300)),
+	]))
+	.unwrap();
+	assert_eq!(rt.balances.balance(&eve_id), 700);
+	assert_eq!(rt.vesting.vesting_balance(&eve_id), 700);
+}
+
+// ---------------------------------------------------------------------------
+// Assets
+// ---------------------------------------------------------------------------
+
+fn signed_assets_call(
+	from: support::keyring::AccountKeyring,
+	nonce: u32,
+	call: assets::Call<Runtime>,
+) -> types::Extrinsic {
+	let call = RuntimeCall::assets(call);
+	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, valid_until(), call)
+}
+
+#[test]
+fn assets_create_mint_transfer_and_delegate() {
+	init();
+	let mut rt = Runtime::new();
+	let an = rt.system.nonce(&Alice.public());
+	let bn = rt.system.nonce(&Bob.public());
+	let id = 7;
+
+	rt.author_block(next_block(&rt, vec![
+		signed_assets_call(Alice, an, assets::Call::create {
+			id,
+			name: "Test Dollar".into(),
+			symbol: "TUSD".into(),
+			decimals: 6,
+		}),
+		signed_assets_call(Alice, an + 1, assets::Call::mint {
+			id,
+			beneficiary: Alice.public(),
+			amount: 1_000,
+		}),
+		// Bob is not the asset's admin.
+		signed_assets_call(Bob, bn, assets::Call::mint {
+			id,
+			beneficiary: Bob.public(),
+			amount: 1_000,
+		}),
+		signed_assets_call(Alice, an + 2, assets::Call::transfer {
+			id,
+			to: Bob.public(),
+			amount: 100,
+		}),
+		signed_assets_call(Alice, an + 3, assets::Call::approve {
+			id,
+			delegate: Bob.public(),
+			amount: 250,
+		}),
+		signed_assets_call(Bob, bn + 1, assets::Call::transfer_from {
+			id,
+			owner: Alice.public(),
+			to: Charlie.public(),
+			amount: 200,
+		}),
+	]))
+	.unwrap();
+
+	assert_eq!(rt.assets.metadata(id).unwrap().symbol, "TUSD");
+	assert_eq!(rt.assets.total_supply(id), 1_000);
+	assert_eq!(rt.assets.balance(id, &Alice.public()), 700);
+	assert_eq!(rt.assets.balance(id, &Bob.public()), 100);
+	assert_eq!(rt.assets.balance(id, &Charlie.public()), 200);
+	assert_eq!(rt.assets.allowance(id, &Alice.public(), &Bob.public()), 50);
+
+	let reloaded = Runtime::new();
+	assert_eq!(reloaded.assets.balance(id, &Charlie.public()), 200);
+	assert_eq!(reloaded.assets.details(id).unwrap().admin, Alice.public());
+}
+
+// ---------------------------------------------------------------------------
+// NFTs
+// ---------------------------------------------------------------------------
+
+fn signed_nfts_call(
+	from: support::keyring::AccountKeyring,
+	nonce: u32,
+	call: nfts::Call<Runtime>,
+) -> types::Extrinsic {
+	let call = RuntimeCall::nfts(call);
+	support::UncheckedExtrinsic::new_signed(&from.signing_key(), nonce, valid_until(), call)
+}
+
+#[test]
+fn nft_certificates_are_minted_traded_and_burned() {
+	init();
+	let mut rt = Runtime::new();
+	let an = rt.system.nonce(&Alice.public());
+	let bn = rt.system.nonce(&Bob.public());
+	let collection = 42;
+	let attributes = vec![("title".to_string(), "Deed #1".to_string())];
+
+	rt.author_block(next_block(&rt, vec![
+		signed_nfts_call(Alice, an, nfts::Call::create_collection { collection }),
+		signed_nfts_call(Alice, an + 1, nfts::Call::mint {
+			collection,
+			item: 1,
+			owner: Alice.public(),
+			attributes: attributes.clone(),
+		}),
+		signed_nfts_call(Alice, an + 2, nfts::Call::mint {
+			collection,
+			item: 2,
+			owner: Alice.public(),
+			attributes: vec![],
+		}),
+		signed_nfts_call(Alice, an + 3, nfts::Call::approve {
+			collection,
+			item: 1,
+			delegate: Some(Bob.public()),
+		}),
+		// Bob, as the approved delegate, hands the certificate to Charlie.
+		signed_nfts_call(Bob, bn, nfts::Call::transfer {
+			collection,
+			item: 1,
+			to: Charlie.public(),
+		}),
+		signed_nfts_call(Alice, an + 4, nfts::Call::burn { collection, item: 2 }),
+	]))
+	.unwrap();
+
+	assert_eq!(rt.nfts.owner(collection, 1), Some(&Charlie.public()));
+	assert_eq!(rt.nfts.item(collection, 1).unwrap().attributes, attributes);
+	assert_eq!(rt.nfts.items_of(&Charlie.public()), [(collection, 1)]);
+	assert!(rt.nfts.items_of(&Alice.public()).is_empty());
+	assert_eq!(rt.nfts.collection(collection).unwrap().items, 1);
+	assert_eq!(Runtime::new().nfts.owner(collection, 1), Some(&Charlie.public()));
+}
+
 // ---------------------------------------------------------------------------
 // Nonce tracking
 // ---------------------------------------------------------------------------
@@ -178,7 +647,7 @@ fn nonce_increments_after_successful_dispatch() {
 	rt.balances.set_balance(&Alice.public(), 1_000);
 	let before = rt.system.nonce(&Alice.public());
 
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, before, Bob, 10)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, before, Bob, 10)])).unwrap();
 
 	assert_eq!(rt.system.nonce(&Alice.public()), before + 1);
 }
@@ -196,7 +665,7 @@ fn nonce_mismatch_extrinsic_is_skipped() {
 	let wrong_nonce_ext = signed_transfer(Alice, runtime_nonce + 100, Bob, 200);
 	assert!(wrong_nonce_ext.verify().is_ok(), "signature itself is valid");
 
-	rt.execute_block(next_block(&rt, vec![wrong_nonce_ext])).unwrap();
+	rt.author_block(next_block(&rt, vec![wrong_nonce_ext])).unwrap();
 
 	// Bob received nothing; extrinsic was skipped.
 	assert_eq!(rt.balances.balance(&Bob.public()), 0);
@@ -210,16 +679,75 @@ fn sequential_nonces_across_blocks() {
 	rt.balances.set_balance(&Bob.public(), 0);
 
 	let n0 = rt.system.nonce(&Alice.public());
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, n0, Bob, 10)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, n0, Bob, 10)])).unwrap();
 	assert_eq!(rt.system.nonce(&Alice.public()), n0 + 1);
 
 	let n1 = rt.system.nonce(&Alice.public());
-	rt.execute_block(next_block(&rt, vec![signed_transfer(Alice, n1, Bob, 10)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_transfer(Alice, n1, Bob, 10)])).unwrap();
 	assert_eq!(rt.system.nonce(&Alice.public()), n0 + 2);
 
 	assert_eq!(rt.balances.balance(&Bob.public()), 20);
 }
 
+// ---------------------------------------------------------------------------
+// Extrinsic index
+// ---------------------------------------------------------------------------
+
+#[test]
+fn included_extrinsics_are_indexed_by_hash() {
+	init();
+	let mut rt = Runtime::new();
+	rt.balances.set_balance(&Alice.public(), 1_000);
+	let nonce = rt.system.nonce(&Alice.public());
+	let ok = signed_transfer(Alice, nonce, Bob, 10);
+	let too_much = signed_transfer(Alice, nonce + 1, Bob, 1_000_000);
+	let stale = signed_transfer(Alice, nonce, Bob, 1);
+	let hashes = [ok.hash(), too_much.hash(), stale.hash()];
+	let block_number = rt.system.block_number() + 1;
+
+	rt.author_block(next_block(&rt, vec![ok, too_much, stale])).unwrap();
+
+	let records: Vec<_> = hashes
+		.iter()
+		.map(|h| support::extrinsic_record::<types::BlockNumber>(h).expect("indexed"))
+		.collect();
+	assert!(records.iter().all(|r| r.block_number == block_number));
+	assert_eq!(records.iter().map(|r| r.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+	assert_eq!(records[0].outcome, support::ExtrinsicOutcome::Applied);
+	assert_eq!(
+		records[1].outcome,
+		support::ExtrinsicOutcome::DispatchFailed("Not enough funds.".into())
+	);
+	assert_eq!(records[2].outcome, support::ExtrinsicOutcome::BadNonce);
+
+	let decoded = types::Extrinsic::decode(&mut &records[0].extrinsic[..]).unwrap();
+	assert_eq!(decoded.hash(), hashes[0]);
+}
+
+#[test]
+fn replayed_extrinsics_keep_their_original_record() {
+	init();
+	let mut rt = Runtime::new();
+	let (judy, account) = funded_claimer(&mut rt, "Judy");
+	let (nonce, until) = (rt.system.nonce(&account), valid_until());
+	let transfer = || {
+		let call = RuntimeCall::balances(balances::Call::transfer { to: Bob.public(), amount: 10 });
+		support::UncheckedExtrinsic::new_signed(&judy, nonce, until, call)
+	};
+	let hash = transfer().hash();
+	let block_number = rt.system.block_number() + 1;
+	rt.author_block(next_block(&rt, vec![transfer()])).unwrap();
+
+	// ed25519 signing is deterministic, so this is the very same extrinsic.
+	let replay = transfer();
+	assert_eq!(replay.hash(), hash);
+	rt.author_block(next_block(&rt, vec![replay])).unwrap();
+
+	let record = support::extrinsic_record::<types::BlockNumber>(&hash).expect("indexed");
+	assert_eq!(record.block_number, block_number);
+	assert_eq!(record.outcome, support::ExtrinsicOutcome::Applied);
+}
+
 // ---------------------------------------------------------------------------
 // Proof of existence
 // ---------------------------------------------------------------------------
@@ -228,44 +756,268 @@ fn sequential_nonces_across_blocks() {
 fn poe_create_claim_recorded_on_chain() {
 	init();
 	let mut rt = Runtime::new();
-	let nonce = rt.system.nonce(&Alice.public());
+	let (alice, alice_id) = funded_claimer(&mut rt, "Alice");
+	let nonce = rt.system.nonce(&alice_id);
 	let claim = "rt-poe-create";
 
-	rt.execute_block(next_block(&rt, vec![signed_claim(Alice, nonce, claim)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_claim(&alice, nonce, claim)])).unwrap();
+
+	assert_eq!(claim_owner(&rt, claim), Some(alice_id));
+}
+
+#[test]
+fn poe_claim_is_stamped_with_block_and_timestamp() {
+	init();
+	let mut rt = Runtime::new();
+	let (alice, alice_id) = funded_claimer(&mut rt, "Alice");
+	let nonce = rt.system.nonce(&alice_id);
+	let digest = support::blake2_256(b"rt-poe-stamped");
+	let call = proof_of_existence::Call::create_claim {
+		claim: digest,
+		metadata: "signed contract, v2".to_string(),
+		expires_at: None,
+	};
+	let block = next_block(&rt, vec![signed_poe_call(&alice, nonce, call)]);
+	let (block_number, timestamp) = (block.header.block_number, block.header.timestamp);
+
+	rt.author_block(block).unwrap();
 
-	assert_eq!(rt.proof_of_existence.get_claim(&claim.to_string()), Some(&Alice.public()));
+	let info = rt.proof_of_existence.get_claim(&digest).expect("claimed");
+	assert_eq!((info.block_number, info.created_at), (block_number, timestamp));
+	assert_eq!(info.metadata, "signed contract, v2");
 }
 
 #[test]
 fn poe_duplicate_claim_is_rejected_at_dispatch() {
 	init();
 	let mut rt = Runtime::new();
-	let a_nonce = rt.system.nonce(&Alice.public());
-	let b_nonce = rt.system.nonce(&Bob.public());
+	let (alice, alice_id) = funded_claimer(&mut rt, "Alice");
+	let (bob, bob_id) = funded_claimer(&mut rt, "Bob");
+	let a_nonce = rt.system.nonce(&alice_id);
+	let b_nonce = rt.system.nonce(&bob_id);
 	let claim = "rt-poe-duplicate";
 
-	rt.execute_block(next_block(&rt, vec![signed_claim(Alice, a_nonce, claim)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_claim(&alice, a_nonce, claim)])).unwrap();
 	// Bob attempts the same claim — block succeeds, dispatch fails silently.
-	rt.execute_block(next_block(&rt, vec![signed_claim(Bob, b_nonce, claim)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_claim(&bob, b_nonce, claim)])).unwrap();
 
-	assert_eq!(rt.proof_of_existence.get_claim(&claim.to_string()), Some(&Alice.public()));
+	assert_eq!(claim_owner(&rt, claim), Some(alice_id));
 }
 
 #[test]
 fn poe_revoke_allows_reclaim_by_new_owner() {
 	init();
 	let mut rt = Runtime::new();
-	let a0 = rt.system.nonce(&Alice.public());
-	let b0 = rt.system.nonce(&Bob.public());
+	let (alice, alice_id) = funded_claimer(&mut rt, "Alice");
+	let (bob, bob_id) = funded_claimer(&mut rt, "Bob");
+	let a0 = rt.system.nonce(&alice_id);
+	let b0 = rt.system.nonce(&bob_id);
 	let claim = "rt-poe-revoke-reclaim";
 
-	rt.execute_block(next_block(&rt, vec![signed_claim(Alice, a0, claim)])).unwrap();
+	rt.author_block(next_block(&rt, vec![signed_claim(&alice, a0, claim)])).unwrap();
 	// a0+1 because Alice's nonce was incremented by the previous block.
-	rt.execute_block(next_block(&rt, vec![signed_revoke(Alice, a0 + 1, claim)])).unwrap();
-	assert_eq!(rt.proof_of_existence.get_claim(&claim.to_string()), None);
+	rt.author_block(next_block(&rt, vec![signed_revoke(&alice, a0 + 1, claim)])).unwrap();
+	assert_eq!(claim_owner(&rt, claim), None);
 
-	rt.execute_block(next_block(&rt, vec![signed_claim(Bob, b0, claim)])).unwrap();
-	assert_eq!(rt.proof_of_existence.get_claim(&claim.to_string()), Some(&Bob.public()));
+	rt.author_block(next_block(&rt, vec![signed_claim(&bob, b0, claim)])).unwrap();
+	assert_eq!(claim_owner(&rt, claim), Some(bob_id));
+}
+
+#[test]
+fn poe_claim_deposit_is_reserved_until_revoked() {
+	init();
+	let mut rt = Runtime::new();
+	let (dave, dave_id) = funded_claimer(&mut rt, "Dave");
+	let nonce = rt.system.nonce(&dave_id);
+	let claim = "rt-poe-deposit";
+
+	rt.author_block(next_block(&rt, vec![signed_claim(&dave, nonce, claim)])).unwrap();
+	assert_eq!((rt.balances.balance(&dave_id), rt.balances.reserved_balance(&dave_id)), (900, 100));
+	assert_eq!(rt.proof_of_existence.claim_count(&dave_id), 1);
+
+	rt.author_block(next_block(&rt, vec![signed_revoke(&dave, nonce + 1, claim)])).unwrap();
+	assert_eq!((rt.balances.balance(&dave_id), rt.balances.reserved_balance(&dave_id)), (1_000, 0));
+}
+
+#[test]
+fn poe_expired_claim_is_removed_at_its_expiry_block() {
+	init();
+	let mut rt = Runtime::new();
+	let (eve, eve_id) = funded_claimer(&mut rt, "Eve");
+	let nonce = rt.system.nonce(&eve_id);
+	let claim = support::blake2_256(b"rt-poe-expiring");
+	let expires_at = rt.system.block_number() + 3;
+	let call = proof_of_existence::Call::create_claim {
+		claim,
+		metadata: String::new(),
+		expires_at: Some(expires_at),
+	};
+
+	rt.author_block(next_block(&rt, vec![signed_poe_call(&eve, nonce, call)])).unwrap();
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+	assert!(rt.proof_of_existence.get_claim(&claim).is_some(), "still valid");
+	assert_eq!(rt.balances.reserved_balance(&eve_id), 100);
+
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+	assert_eq!(rt.system.block_number(), expires_at);
+	assert_eq!(rt.proof_of_existence.get_claim(&claim), None);
+	assert_eq!(rt.balances.reserved_balance(&eve_id), 0, "deposit returned");
+}
+
+#[test]
+fn poe_delegate_transfers_claim_and_history_shows_provenance() {
+	init();
+	let mut rt = Runtime::new();
+	let (alice, alice_id) = funded_claimer(&mut rt, "Alice");
+	let (charlie, charlie_id) = (claimer("Charlie"), claimer("Charlie").public().into_account());
+	let bob_id = claimer("Bob").public().into_account();
+	let a0 = rt.system.nonce(&alice_id);
+	let c0 = rt.system.nonce(&charlie_id);
+	let claim = support::blake2_256(b"rt-poe-transfer");
+
+	rt.author_block(next_block(&rt, vec![signed_claim(&alice, a0, "rt-poe-transfer")])).unwrap();
+	let created = rt.system.block_number();
+	let delegate = proof_of_existence::Call::set_delegate { claim, delegate: Some(charlie_id) };
+	let transfer = proof_of_existence::Call::transfer_claim { claim, to: bob_id };
+	rt.author_block(next_block(&rt, vec![
+		signed_poe_call(&alice, a0 + 1, delegate),
+		signed_poe_call(&charlie, c0, transfer),
+	]))
+	.unwrap();
+
+	assert_eq!(claim_owner(&rt, "rt-poe-transfer"), Some(bob_id));
+	let history: Vec<_> = rt
+		.proof_of_existence
+		.history(&claim)
+		.iter()
+		.map(|record| (record.owner, record.block_number))
+		.collect();
+	assert_eq!(history, [(alice_id, created), (bob_id, created + 1)]);
+	assert!(rt.proof_of_existence.claims_of(&bob_id).contains(&claim));
+	assert!(!rt.proof_of_existence.claims_of(&alice_id).contains(&claim));
+}
+
+#[test]
+fn poe_claim_proof_verifies_against_the_sealed_header() {
+	init();
+	let mut rt = Runtime::new();
+	let (frank, frank_id) = funded_claimer(&mut rt, "Frank");
+	let nonce = rt.system.nonce(&frank_id);
+	let digest = support::blake2_256(b"rt-poe-proof");
+	let before = rt.author_block(next_block(&rt, vec![])).unwrap().header;
+
+	let block = next_block(&rt, vec![signed_claim(&frank, nonce, "rt-poe-proof")]);
+	let header = rt.author_block(block).unwrap().header;
+	assert_eq!(header.state_root, rt.proof_of_existence.state_root());
+	assert_eq!(rt.system.header().encode(), header.encode());
+
+	let proof = rt.proof_of_existence.prove_claim(&digest).expect("claimed");
+	let decoded = proof_of_existence::ClaimProofOf::<Runtime>::decode(&mut &proof.encode()[..]);
+	assert_eq!(decoded.as_ref(), Ok(&proof));
+	assert!(proof_of_existence::verify_proof(&header, &proof));
+	assert!(!proof_of_existence::verify_proof(&before, &proof), "not yet claimed then");
+
+	let mut forged = proof;
+	forged.info.owner = Alice.public();
+	assert!(!proof_of_existence::verify_proof(&header, &forged));
+}
+
+// ---------------------------------------------------------------------------
+// Sudo
+// ---------------------------------------------------------------------------
+
+fn signed_sudo(from: &support::Pair, nonce: u32, call: RuntimeCall) -> types::Extrinsic {
+	let call = RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(call) });
+	support::UncheckedExtrinsic::new_signed(from, nonce, valid_until(), call)
+}
+
+#[test]
+fn sudo_dispatches_as_root_and_records_the_outcome() {
+	init();
+	let mut rt = Runtime::new();
+	// Tests share the database, so each sudo test installs a key of its own.
+	let (key, key_account) = funded_claimer(&mut rt, "Grace");
+	rt.sudo.initialize_key(key_account);
+	rt.balances.set_balance(&Charlie.public(), 500);
+	let (claimer, claimer_account) = funded_claimer(&mut rt, "Heidi");
+	let cn = rt.system.nonce(&claimer_account);
+	rt.author_block(next_block(&rt, vec![signed_claim(&claimer, cn, "abusive claim")])).unwrap();
+	let claim = support::blake2_256(b"abusive claim");
+	let kn = rt.system.nonce(&key_account);
+	let bn = rt.system.nonce(&Bob.public());
+
+	let set_balance = balances::Call::force_set_balance { who: Charlie.public(), new_free: 50 };
+	let revoke = proof_of_existence::Call::force_revoke_claim { claim };
+	let overdraw = balances::Call::force_transfer {
+		source: Charlie.public(),
+		to: key_account,
+		amount: 1_000,
+	};
+	rt.author_block(next_block(&rt, vec![
+		signed_sudo(&key, kn, RuntimeCall::balances(set_balance)),
+		signed_sudo(&key, kn + 1, RuntimeCall::proof_of_existence(revoke)),
+		signed_sudo(&key, kn + 2, RuntimeCall::balances(overdraw)),
+		// Bob does not hold the key, so his sudo call fails without being dispatched.
+		signed_sudo(&Bob.signing_key(), bn, RuntimeCall::sudo(sudo::Call::set_key {
+			new: Bob.public(),
+		})),
+	]))
+	.unwrap();
+
+	assert_eq!(rt.balances.balance(&Charlie.public()), 50);
+	assert!(rt.proof_of_existence.get_claim(&claim).is_none());
+	assert_eq!(rt.balances.reserved_balance(&claimer_account), 0, "the deposit is returned");
+	assert_eq!(rt.sudo.key(), Some(&key_account));
+
+	let ok = sudo::SudoOutcome { key: key_account, result: Ok(()) };
+	let failed =
+		sudo::SudoOutcome { key: key_account, result: Err("Not enough funds.".to_string()) };
+	assert_eq!(rt.sudo.outcomes(rt.system.block_number()), vec![&ok, &ok, &failed]);
+}
+
+#[test]
+fn sudo_dispatched_directly_takes_effect_at_once() {
+	init();
+	let mut rt = Runtime::new();
+	let (_, key_account) = funded_claimer(&mut rt, "Niaj");
+	rt.sudo.initialize_key(key_account);
+	let (_, target) = funded_claimer(&mut rt, "Olivia");
+	rt.author_block(next_block(&rt, vec![])).unwrap();
+
+	// Dispatched straight from runtime code, outside any block.
+	let set_balance = balances::Call::force_set_balance { who: target, new_free: 42 };
+	let call = RuntimeCall::sudo(sudo::Call::sudo {
+		call: Box::new(RuntimeCall::balances(set_balance)),
+	});
+	rt.dispatch(RuntimeOrigin::Signed(key_account), call).unwrap();
+
+	assert_eq!(rt.balances.balance(&target), 42);
+	let ok = sudo::SudoOutcome { key: key_account, result: Ok(()) };
+	assert_eq!(rt.sudo.outcomes(rt.system.block_number()).last(), Some(&&ok));
+}
+
+#[test]
+fn sudo_set_key_hands_over_the_key() {
+	init();
+	let mut rt = Runtime::new();
+	let (key, key_account) = funded_claimer(&mut rt, "Ivan");
+	rt.sudo.initialize_key(key_account);
+	let nonce = rt.system.nonce(&key_account);
+
+	let set_key = sudo::Call::set_key { new: Charlie.public() };
+	let call = RuntimeCall::sudo(set_key);
+	let ext = support::UncheckedExtrinsic::new_signed(&key, nonce, valid_until(), call);
+	rt.author_block(next_block(&rt, vec![ext])).unwrap();
+	assert_eq!(rt.sudo.key(), Some(&Charlie.public()));
+
+	// The old key can no longer sudo.
+	let call = RuntimeCall::balances(balances::Call::force_set_balance {
+		who: key_account,
+		new_free: 1_000_000,
+	});
+	rt.author_block(next_block(&rt, vec![signed_sudo(&key, nonce + 1, call)])).unwrap();
+	assert_eq!(rt.balances.balance(&key_account), 1_000);
+	assert!(rt.sudo.outcomes(rt.system.block_number()).is_empty());
 }
 
 // ---------------------------------------------------------------------------
@@ -277,6 +1029,7 @@ fn maybe_apply_genesis_idempotent() {
 	init();
 	let mut rt = Runtime::new();
 	let before = rt.system.block_number();
+	let alice_before = rt.balances.balance(&Alice.public());
 
 	maybe_apply_genesis(&mut rt);
 	let after_first = rt.system.block_number();
@@ -288,6 +1041,7 @@ fn maybe_apply_genesis_idempotent() {
 	// If the chain was at block 0, genesis advanced it to 1 and funded accounts.
 	if before == 0 {
 		assert_eq!(after_first, 1);
-		assert_eq!(rt.balances.balance(&Alice.public()), 1_000_000);
+		assert_eq!(rt.balances.balance(&Alice.public()), alice_before + 1_000_000);
+		assert_eq!(rt.sudo.key(), Some(&Alice.public()));
 	}
 }